@@ -61,7 +61,8 @@ async function initContract() {
             args: {
                 name: 'wise',
                 verifier: 'wise-verifier.testnet',
-                currencies: ['USD', 'EUR', 'GBP']
+                currencies: ['USD', 'EUR', 'GBP'],
+                server_names: ['wise.com', 'api.wise.com', 'api.transferwise.com']
             },
             gas: '30000000000000',
         });
@@ -75,7 +76,8 @@ async function initContract() {
             args: {
                 name: 'venmo',
                 verifier: 'venmo-verifier.testnet',
-                currencies: ['USD']
+                currencies: ['USD'],
+                server_names: ['venmo.com', 'api.venmo.com']
             },
             gas: '30000000000000',
         });
@@ -89,7 +91,8 @@ async function initContract() {
             args: {
                 name: 'revolut',
                 verifier: 'revolut-verifier.testnet',
-                currencies: ['USD', 'EUR', 'GBP', 'SGD']
+                currencies: ['USD', 'EUR', 'GBP', 'SGD'],
+                server_names: ['app.revolut.com', 'revolut.com']
             },
             gas: '30000000000000',
         });
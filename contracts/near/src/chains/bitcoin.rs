@@ -1,6 +1,22 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::env;
 use near_sdk::serde::{Deserialize, Serialize};
 
+#[cfg(feature = "abi")]
+use near_sdk::schemars::JsonSchema;
+
+use super::secp256k1::{sub_be, SECP256K1_HALF_ORDER, SECP256K1_ORDER};
+
+/// BIP-144 marker and flag bytes that follow the version in a SegWit transaction.
+const SEGWIT_MARKER: u8 = 0x00;
+const SEGWIT_FLAG: u8 = 0x01;
+
+pub const SIGHASH_DEFAULT: u8 = 0x00;
+pub const SIGHASH_ALL: u8 = 0x01;
+pub const SIGHASH_NONE: u8 = 0x02;
+pub const SIGHASH_SINGLE: u8 = 0x03;
+pub const SIGHASH_ANYONECANPAY: u8 = 0x80;
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Utxo {
@@ -9,7 +25,7 @@ pub struct Utxo {
     pub value: u64,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 #[serde(crate = "near_sdk::serde")]
 pub struct BitcoinTransaction {
     pub version: i32,
@@ -18,57 +34,1141 @@ pub struct BitcoinTransaction {
     pub locktime: u32,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+/// `txid` is in the usual display (big-endian) hex; it is byte-reversed on the wire.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 #[serde(crate = "near_sdk::serde")]
 pub struct BitcoinInput {
     pub txid: String,
     pub vout: u32,
     pub script_sig: Vec<u8>,
     pub sequence: u32,
+    #[serde(default)]
+    pub witness: Vec<Vec<u8>>,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 #[serde(crate = "near_sdk::serde")]
 pub struct BitcoinOutput {
     pub value: u64,
     pub script_pubkey: Vec<u8>,
 }
 
-// Basic implementation for BTC transaction serialization
-pub fn serialize_transaction(tx: &BitcoinTransaction) -> Vec<u8> {
+/// Appends `value` as a Bitcoin CompactSize integer.
+pub fn write_compact_size(data: &mut Vec<u8>, value: u64) {
+    match value {
+        0..=0xfc => data.push(value as u8),
+        0xfd..=0xffff => {
+            data.push(0xfd);
+            data.extend_from_slice(&(value as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            data.push(0xfe);
+            data.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        _ => {
+            data.push(0xff);
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+fn write_var_bytes(data: &mut Vec<u8>, bytes: &[u8]) {
+    write_compact_size(data, bytes.len() as u64);
+    data.extend_from_slice(bytes);
+}
+
+/// Decodes a display-order txid into its 32-byte wire (little-endian) form.
+pub fn decode_txid(txid: &str) -> Result<[u8; 32], String> {
+    let bytes = hex::decode(txid.trim()).map_err(|_| format!("invalid txid hex: {}", txid))?;
+    let mut wire: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| format!("txid must be 32 bytes: {}", txid))?;
+    wire.reverse();
+    Ok(wire)
+}
+
+fn encode_txid(wire: &[u8; 32]) -> String {
+    let mut display = *wire;
+    display.reverse();
+    hex::encode(display)
+}
+
+/// Double SHA-256.
+pub fn hash256(data: &[u8]) -> [u8; 32] {
+    env::sha256_array(&env::sha256_array(data))
+}
+
+fn has_witness(tx: &BitcoinTransaction) -> bool {
+    tx.inputs.iter().any(|input| !input.witness.is_empty())
+}
+
+fn encode(tx: &BitcoinTransaction, include_witness: bool) -> Result<Vec<u8>, String> {
+    let segwit = include_witness && has_witness(tx);
     let mut data = Vec::new();
-    // Version
     data.extend_from_slice(&tx.version.to_le_bytes());
+    if segwit {
+        data.push(SEGWIT_MARKER);
+        data.push(SEGWIT_FLAG);
+    }
 
-    // Inputs
-    data.push(tx.inputs.len() as u8); // VarInt simplified
+    write_compact_size(&mut data, tx.inputs.len() as u64);
     for input in &tx.inputs {
-        let txid_bytes = hex::decode(&input.txid).unwrap_or(vec![0; 32]);
-        // TXID is little-endian in Bitcoin protocol
-        let mut txid_le = txid_bytes.clone();
-        txid_le.reverse();
-        data.extend_from_slice(&txid_le);
-
+        data.extend_from_slice(&decode_txid(&input.txid)?);
         data.extend_from_slice(&input.vout.to_le_bytes());
-        // ScriptSig length + ScriptSig
-        data.push(input.script_sig.len() as u8);
-        data.extend_from_slice(&input.script_sig);
-
+        write_var_bytes(&mut data, &input.script_sig);
         data.extend_from_slice(&input.sequence.to_le_bytes());
     }
 
-    // Outputs
-    data.push(tx.outputs.len() as u8); // VarInt simplified
+    write_compact_size(&mut data, tx.outputs.len() as u64);
     for output in &tx.outputs {
         data.extend_from_slice(&output.value.to_le_bytes());
+        write_var_bytes(&mut data, &output.script_pubkey);
+    }
 
-        // ScriptPubKey length + ScriptPubKey
-        data.push(output.script_pubkey.len() as u8);
-        data.extend_from_slice(&output.script_pubkey);
+    if segwit {
+        for input in &tx.inputs {
+            write_compact_size(&mut data, input.witness.len() as u64);
+            for item in &input.witness {
+                write_var_bytes(&mut data, item);
+            }
+        }
     }
 
-    // Locktime
     data.extend_from_slice(&tx.locktime.to_le_bytes());
+    Ok(data)
+}
+
+/// Network serialization. Uses the BIP-144 SegWit layout when any input
+/// carries a witness, the legacy layout otherwise.
+pub fn serialize_transaction(tx: &BitcoinTransaction) -> Result<Vec<u8>, String> {
+    encode(tx, true)
+}
+
+/// Serialization without witness data, as committed to by the txid.
+pub fn serialize_transaction_without_witness(tx: &BitcoinTransaction) -> Result<Vec<u8>, String> {
+    encode(tx, false)
+}
+
+/// Transaction id in display hex (hash of the witness-stripped serialization).
+pub fn txid(tx: &BitcoinTransaction) -> Result<String, String> {
+    Ok(encode_txid(&hash256(&encode(tx, false)?)))
+}
+
+/// Witness transaction id in display hex. Equals the txid for legacy transactions.
+pub fn wtxid(tx: &BitcoinTransaction) -> Result<String, String> {
+    Ok(encode_txid(&hash256(&encode(tx, true)?)))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| "unexpected end of transaction".to_string())?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.read_bytes(N)?.try_into().unwrap())
+    }
+
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.data.get(self.position + offset).copied()
+    }
+
+    fn read_compact_size(&mut self) -> Result<u64, String> {
+        let (value, min) = match self.read_array::<1>()?[0] {
+            0xfd => (u16::from_le_bytes(self.read_array()?) as u64, 0xfd),
+            0xfe => (u32::from_le_bytes(self.read_array()?) as u64, 0x1_0000),
+            0xff => (u64::from_le_bytes(self.read_array()?), 0x1_0000_0000),
+            prefix => (prefix as u64, 0),
+        };
+        if value < min {
+            return Err("non-canonical CompactSize".to_string());
+        }
+        Ok(value)
+    }
+
+    fn read_count(&mut self) -> Result<usize, String> {
+        let count = self.read_compact_size()?;
+        // Every counted element takes at least one byte, so this bounds
+        // allocations by the input size.
+        if count > (self.data.len() - self.position) as u64 {
+            return Err("CompactSize exceeds remaining data".to_string());
+        }
+        Ok(count as usize)
+    }
+
+    fn read_var_bytes(&mut self) -> Result<Vec<u8>, String> {
+        let len = self.read_count()?;
+        Ok(self.read_bytes(len)?.to_vec())
+    }
+}
+
+/// Parses a legacy or BIP-144 SegWit transaction. Trailing bytes are rejected.
+pub fn deserialize_transaction(data: &[u8]) -> Result<BitcoinTransaction, String> {
+    let mut reader = Reader { data, position: 0 };
+    let version = i32::from_le_bytes(reader.read_array()?);
+
+    let segwit = reader.peek(0) == Some(SEGWIT_MARKER) && reader.peek(1) == Some(SEGWIT_FLAG);
+    if segwit {
+        reader.read_bytes(2)?;
+    }
+
+    let input_count = reader.read_count()?;
+    let mut inputs = Vec::with_capacity(input_count);
+    for _ in 0..input_count {
+        let txid = encode_txid(&reader.read_array()?);
+        let vout = u32::from_le_bytes(reader.read_array()?);
+        let script_sig = reader.read_var_bytes()?;
+        let sequence = u32::from_le_bytes(reader.read_array()?);
+        inputs.push(BitcoinInput {
+            txid,
+            vout,
+            script_sig,
+            sequence,
+            witness: Vec::new(),
+        });
+    }
+
+    let output_count = reader.read_count()?;
+    let mut outputs = Vec::with_capacity(output_count);
+    for _ in 0..output_count {
+        let value = u64::from_le_bytes(reader.read_array()?);
+        let script_pubkey = reader.read_var_bytes()?;
+        outputs.push(BitcoinOutput {
+            value,
+            script_pubkey,
+        });
+    }
+
+    if segwit {
+        for input in inputs.iter_mut() {
+            let item_count = reader.read_count()?;
+            for _ in 0..item_count {
+                input.witness.push(reader.read_var_bytes()?);
+            }
+        }
+        if inputs.iter().all(|input| input.witness.is_empty()) {
+            return Err("SegWit transaction without witness data".to_string());
+        }
+    }
+
+    let locktime = u32::from_le_bytes(reader.read_array()?);
+    if reader.position != data.len() {
+        return Err("trailing bytes after transaction".to_string());
+    }
 
+    Ok(BitcoinTransaction {
+        version,
+        inputs,
+        outputs,
+        locktime,
+    })
+}
+
+fn outpoint(input: &BitcoinInput) -> Result<Vec<u8>, String> {
+    let mut data = decode_txid(&input.txid)?.to_vec();
+    data.extend_from_slice(&input.vout.to_le_bytes());
+    Ok(data)
+}
+
+fn serialize_output(output: &BitcoinOutput) -> Vec<u8> {
+    let mut data = output.value.to_le_bytes().to_vec();
+    write_var_bytes(&mut data, &output.script_pubkey);
     data
 }
+
+fn tagged_hash(tag: &str, data: &[u8]) -> [u8; 32] {
+    let tag_hash = env::sha256_array(tag.as_bytes());
+    let mut preimage = Vec::with_capacity(64 + data.len());
+    preimage.extend_from_slice(&tag_hash);
+    preimage.extend_from_slice(&tag_hash);
+    preimage.extend_from_slice(data);
+    env::sha256_array(&preimage)
+}
+
+/// BIP-143 scriptCode for a P2WPKH input: the equivalent P2PKH script.
+pub fn p2wpkh_script_code(pubkey_hash: &[u8; 20]) -> Vec<u8> {
+    let mut script = vec![0x76, 0xa9, 0x14];
+    script.extend_from_slice(pubkey_hash);
+    script.extend_from_slice(&[0x88, 0xac]);
+    script
+}
+
+/// BIP-143 digest for SegWit v0 input `input_index` spending `amount` satoshis.
+pub fn segwit_v0_sighash(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    script_code: &[u8],
+    amount: u64,
+    sighash_type: u8,
+) -> Result<[u8; 32], String> {
+    let base_type = sighash_type & !SIGHASH_ANYONECANPAY;
+    if !(SIGHASH_ALL..=SIGHASH_SINGLE).contains(&base_type) {
+        return Err(format!("unsupported sighash type: {:#04x}", sighash_type));
+    }
+    let input = tx
+        .inputs
+        .get(input_index)
+        .ok_or_else(|| "input index out of range".to_string())?;
+    let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+
+    let hash_prevouts = if anyone_can_pay {
+        [0u8; 32]
+    } else {
+        let mut data = Vec::new();
+        for input in &tx.inputs {
+            data.extend_from_slice(&outpoint(input)?);
+        }
+        hash256(&data)
+    };
+    let hash_sequence = if anyone_can_pay || base_type != SIGHASH_ALL {
+        [0u8; 32]
+    } else {
+        let data: Vec<u8> = tx
+            .inputs
+            .iter()
+            .flat_map(|input| input.sequence.to_le_bytes())
+            .collect();
+        hash256(&data)
+    };
+    let hash_outputs = match base_type {
+        SIGHASH_ALL => hash256(
+            &tx.outputs
+                .iter()
+                .flat_map(serialize_output)
+                .collect::<Vec<u8>>(),
+        ),
+        SIGHASH_SINGLE if input_index < tx.outputs.len() => {
+            hash256(&serialize_output(&tx.outputs[input_index]))
+        }
+        _ => [0u8; 32],
+    };
+
+    let mut preimage = Vec::new();
+    preimage.extend_from_slice(&tx.version.to_le_bytes());
+    preimage.extend_from_slice(&hash_prevouts);
+    preimage.extend_from_slice(&hash_sequence);
+    preimage.extend_from_slice(&outpoint(input)?);
+    write_var_bytes(&mut preimage, script_code);
+    preimage.extend_from_slice(&amount.to_le_bytes());
+    preimage.extend_from_slice(&input.sequence.to_le_bytes());
+    preimage.extend_from_slice(&hash_outputs);
+    preimage.extend_from_slice(&tx.locktime.to_le_bytes());
+    preimage.extend_from_slice(&(sighash_type as u32).to_le_bytes());
+    Ok(hash256(&preimage))
+}
+
+/// SIGHASH_ALL payload for the MPC `sign` call on a P2WPKH input.
+pub fn p2wpkh_sighash(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    pubkey_hash: &[u8; 20],
+    amount: u64,
+) -> Result<[u8; 32], String> {
+    segwit_v0_sighash(
+        tx,
+        input_index,
+        &p2wpkh_script_code(pubkey_hash),
+        amount,
+        SIGHASH_ALL,
+    )
+}
+
+/// BIP-341 key-path signature message for Taproot input `input_index`,
+/// starting with the 0x00 epoch byte. `prevouts` are the outputs being spent,
+/// one per input and in input order.
+pub fn taproot_key_path_sig_msg(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    prevouts: &[BitcoinOutput],
+    sighash_type: u8,
+) -> Result<Vec<u8>, String> {
+    let base_type = sighash_type & !SIGHASH_ANYONECANPAY;
+    if base_type > SIGHASH_SINGLE || sighash_type == SIGHASH_ANYONECANPAY {
+        return Err(format!("unsupported sighash type: {:#04x}", sighash_type));
+    }
+    let input = tx
+        .inputs
+        .get(input_index)
+        .ok_or_else(|| "input index out of range".to_string())?;
+    if prevouts.len() != tx.inputs.len() {
+        return Err("one prevout is required per input".to_string());
+    }
+    let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+
+    let mut message = vec![0x00, sighash_type];
+    message.extend_from_slice(&tx.version.to_le_bytes());
+    message.extend_from_slice(&tx.locktime.to_le_bytes());
+    if !anyone_can_pay {
+        let mut outpoints = Vec::new();
+        for input in &tx.inputs {
+            outpoints.extend_from_slice(&outpoint(input)?);
+        }
+        let amounts: Vec<u8> = prevouts
+            .iter()
+            .flat_map(|prevout| prevout.value.to_le_bytes())
+            .collect();
+        let mut script_pubkeys = Vec::new();
+        for prevout in prevouts {
+            write_var_bytes(&mut script_pubkeys, &prevout.script_pubkey);
+        }
+        let sequences: Vec<u8> = tx
+            .inputs
+            .iter()
+            .flat_map(|input| input.sequence.to_le_bytes())
+            .collect();
+        message.extend_from_slice(&env::sha256_array(&outpoints));
+        message.extend_from_slice(&env::sha256_array(&amounts));
+        message.extend_from_slice(&env::sha256_array(&script_pubkeys));
+        message.extend_from_slice(&env::sha256_array(&sequences));
+    }
+    if base_type != SIGHASH_NONE && base_type != SIGHASH_SINGLE {
+        let outputs: Vec<u8> = tx.outputs.iter().flat_map(serialize_output).collect();
+        message.extend_from_slice(&env::sha256_array(&outputs));
+    }
+
+    // spend_type: key path, no annex.
+    message.push(0x00);
+    if anyone_can_pay {
+        let prevout = &prevouts[input_index];
+        message.extend_from_slice(&outpoint(input)?);
+        message.extend_from_slice(&prevout.value.to_le_bytes());
+        write_var_bytes(&mut message, &prevout.script_pubkey);
+        message.extend_from_slice(&input.sequence.to_le_bytes());
+    } else {
+        message.extend_from_slice(&(input_index as u32).to_le_bytes());
+    }
+    if base_type == SIGHASH_SINGLE {
+        let output = tx
+            .outputs
+            .get(input_index)
+            .ok_or_else(|| "SIGHASH_SINGLE without a matching output".to_string())?;
+        message.extend_from_slice(&env::sha256_array(&serialize_output(output)));
+    }
+
+    Ok(message)
+}
+
+/// BIP-341 key-path digest for Taproot input `input_index`: the TapSighash
+/// tagged hash of `taproot_key_path_sig_msg`.
+pub fn taproot_key_path_sighash(
+    tx: &BitcoinTransaction,
+    input_index: usize,
+    prevouts: &[BitcoinOutput],
+    sighash_type: u8,
+) -> Result<[u8; 32], String> {
+    let message = taproot_key_path_sig_msg(tx, input_index, prevouts, sighash_type)?;
+    Ok(tagged_hash("TapSighash", &message))
+}
+
+fn der_integer(value: &[u8; 32]) -> Vec<u8> {
+    let start = value
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(value.len() - 1);
+    let mut integer = Vec::with_capacity(35);
+    integer.push(0x02);
+    if value[start] & 0x80 != 0 {
+        integer.push((value.len() - start + 1) as u8);
+        integer.push(0x00);
+    } else {
+        integer.push((value.len() - start) as u8);
+    }
+    integer.extend_from_slice(&value[start..]);
+    integer
+}
+
+/// DER-encodes an ECDSA signature, flipping `s` to the low half of the
+/// curve order as required by BIP-146.
+pub fn der_encode_signature(r: &[u8; 32], s: &[u8; 32]) -> Vec<u8> {
+    let s = if *s > SECP256K1_HALF_ORDER {
+        sub_be(&SECP256K1_ORDER, s)
+    } else {
+        *s
+    };
+    let r = der_integer(r);
+    let s = der_integer(&s);
+    let mut signature = vec![0x30, (r.len() + s.len()) as u8];
+    signature.extend_from_slice(&r);
+    signature.extend_from_slice(&s);
+    signature
+}
+
+/// `r` of an MPC signature: the x-coordinate of the returned compressed
+/// `big_r` point.
+pub fn mpc_signature_r(big_r_affine_point: &str) -> Result<[u8; 32], String> {
+    let point =
+        hex::decode(big_r_affine_point.trim()).map_err(|_| "invalid big_r hex".to_string())?;
+    if point.len() != 33 || (point[0] != 0x02 && point[0] != 0x03) {
+        return Err("big_r must be a compressed secp256k1 point".to_string());
+    }
+    Ok(point[1..].try_into().unwrap())
+}
+
+/// P2WPKH witness `<der_signature || sighash_type> <compressed_pubkey>`.
+pub fn p2wpkh_witness(
+    r: &[u8; 32],
+    s: &[u8; 32],
+    sighash_type: u8,
+    public_key: &[u8],
+) -> Result<Vec<Vec<u8>>, String> {
+    if public_key.len() != 33 || (public_key[0] != 0x02 && public_key[0] != 0x03) {
+        return Err("P2WPKH requires a compressed public key".to_string());
+    }
+    let mut signature = der_encode_signature(r, s);
+    signature.push(sighash_type);
+    Ok(vec![signature, public_key.to_vec()])
+}
+
+/// Taproot key-path witness from a BIP-340 signature. The sighash byte is
+/// only appended for types other than SIGHASH_DEFAULT.
+pub fn taproot_key_path_witness(signature: &[u8; 64], sighash_type: u8) -> Vec<Vec<u8>> {
+    let mut witness = signature.to_vec();
+    if sighash_type != SIGHASH_DEFAULT {
+        witness.push(sighash_type);
+    }
+    vec![witness]
+}
+
+/// Network an address must belong to. Testnet and signet share address prefixes.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub enum BitcoinNetwork {
+    Mainnet,
+    Testnet,
+    Signet,
+}
+
+impl BitcoinNetwork {
+    fn bech32_hrp(self) -> &'static str {
+        match self {
+            BitcoinNetwork::Mainnet => "bc",
+            BitcoinNetwork::Testnet | BitcoinNetwork::Signet => "tb",
+        }
+    }
+
+    fn base58_versions(self) -> (u8, u8) {
+        match self {
+            BitcoinNetwork::Mainnet => (0x00, 0x05),
+            BitcoinNetwork::Testnet | BitcoinNetwork::Signet => (0x6f, 0xc4),
+        }
+    }
+}
+
+/// Decoded standard output destination.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BitcoinAddress {
+    P2pkh([u8; 20]),
+    P2sh([u8; 20]),
+    P2wpkh([u8; 20]),
+    P2wsh([u8; 32]),
+    P2tr([u8; 32]),
+}
+
+impl BitcoinAddress {
+    pub fn script_pubkey(&self) -> Vec<u8> {
+        match self {
+            BitcoinAddress::P2pkh(hash) => p2wpkh_script_code(hash),
+            BitcoinAddress::P2sh(hash) => {
+                let mut script = vec![0xa9, 0x14];
+                script.extend_from_slice(hash);
+                script.push(0x87);
+                script
+            }
+            BitcoinAddress::P2wpkh(program) => witness_script(0x00, program),
+            BitcoinAddress::P2wsh(program) => witness_script(0x00, program),
+            BitcoinAddress::P2tr(program) => witness_script(0x51, program),
+        }
+    }
+}
+
+fn witness_script(version_opcode: u8, program: &[u8]) -> Vec<u8> {
+    let mut script = vec![version_opcode, program.len() as u8];
+    script.extend_from_slice(program);
+    script
+}
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+const MAX_ADDRESS_LEN: usize = 90;
+
+fn base58_decode(value: &str) -> Result<Vec<u8>, String> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for character in value.bytes() {
+        let mut carry = BASE58_ALPHABET
+            .iter()
+            .position(|candidate| *candidate == character)
+            .ok_or_else(|| "invalid base58 character".to_string())? as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    let leading_zeros = value.bytes().take_while(|byte| *byte == b'1').count();
+    let mut decoded = vec![0u8; leading_zeros];
+    decoded.extend_from_slice(&bytes);
+    Ok(decoded)
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATORS: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ff_ffff) << 5) ^ *value as u32;
+        for (index, generator) in GENERATORS.iter().enumerate() {
+            if (top >> index) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+fn decode_segwit_address(address: &str, network: BitcoinNetwork) -> Result<BitcoinAddress, String> {
+    if address.bytes().any(|byte| byte.is_ascii_lowercase())
+        && address.bytes().any(|byte| byte.is_ascii_uppercase())
+    {
+        return Err("mixed-case bech32 address".to_string());
+    }
+    let address = address.to_ascii_lowercase();
+    let separator = address
+        .rfind('1')
+        .ok_or_else(|| "missing bech32 separator".to_string())?;
+    let (hrp, data) = (&address[..separator], &address[separator + 1..]);
+    if hrp != network.bech32_hrp() {
+        return Err("address is for a different network".to_string());
+    }
+    if data.len() < 7 {
+        return Err("bech32 data too short".to_string());
+    }
+    let values = data
+        .bytes()
+        .map(|character| {
+            BECH32_CHARSET
+                .iter()
+                .position(|candidate| *candidate == character)
+                .map(|value| value as u8)
+                .ok_or_else(|| "invalid bech32 character".to_string())
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+
+    let mut checked = Vec::with_capacity(hrp.len() * 2 + 1 + values.len());
+    checked.extend(hrp.bytes().map(|byte| byte >> 5));
+    checked.push(0);
+    checked.extend(hrp.bytes().map(|byte| byte & 0x1f));
+    checked.extend_from_slice(&values);
+    let checksum = bech32_polymod(&checked);
+
+    let witness_version = values[0];
+    let mut program = Vec::new();
+    let (mut accumulator, mut bits) = (0u32, 0u32);
+    for value in &values[1..values.len() - 6] {
+        accumulator = (accumulator << 5) | *value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            program.push((accumulator >> bits) as u8);
+        }
+    }
+    if bits >= 5 || accumulator & ((1 << bits) - 1) != 0 {
+        return Err("invalid bech32 padding".to_string());
+    }
+
+    match (witness_version, checksum) {
+        (0, BECH32_CONST) => match program.len() {
+            20 => Ok(BitcoinAddress::P2wpkh(program.try_into().unwrap())),
+            32 => Ok(BitcoinAddress::P2wsh(program.try_into().unwrap())),
+            _ => Err("invalid witness v0 program length".to_string()),
+        },
+        (1, BECH32M_CONST) => match program.len() {
+            32 => Ok(BitcoinAddress::P2tr(program.try_into().unwrap())),
+            _ => Err("invalid witness v1 program length".to_string()),
+        },
+        (0, _) | (1, _) => Err("invalid bech32 checksum".to_string()),
+        _ => Err("unsupported witness version".to_string()),
+    }
+}
+
+/// Decodes a base58 P2PKH/P2SH or bech32(m) P2WPKH/P2WSH/P2TR address,
+/// rejecting addresses that belong to another network.
+pub fn parse_address(address: &str, network: BitcoinNetwork) -> Result<BitcoinAddress, String> {
+    let address = address.trim();
+    if address.is_empty() || address.len() > MAX_ADDRESS_LEN {
+        return Err("invalid address length".to_string());
+    }
+    let lowercase = address.to_ascii_lowercase();
+    if lowercase.starts_with("bc1") || lowercase.starts_with("tb1") {
+        return decode_segwit_address(address, network);
+    }
+
+    let decoded = base58_decode(address)?;
+    if decoded.len() != 25 {
+        return Err("invalid base58 address length".to_string());
+    }
+    let (payload, checksum) = decoded.split_at(21);
+    if hash256(payload)[..4] != *checksum {
+        return Err("invalid base58 checksum".to_string());
+    }
+    let hash: [u8; 20] = payload[1..].try_into().unwrap();
+    let (p2pkh_version, p2sh_version) = network.base58_versions();
+    match payload[0] {
+        version if version == p2pkh_version => Ok(BitcoinAddress::P2pkh(hash)),
+        version if version == p2sh_version => Ok(BitcoinAddress::P2sh(hash)),
+        _ => Err("address is for a different network".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Block 0 coinbase.
+    const GENESIS_COINBASE: &str = "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+
+    // BIP-143 "Native P2WPKH" signed transaction (one P2PK input, one P2WPKH input).
+    const BIP143_P2WPKH: &str = "01000000000102fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f00000000494830450221008b9d1dc26ba6a9cb62127b02742fa9d754cd3bebf337f7a55d114c8e5cdd30be022040529b194ba3f9281a99f2b1c0a19c0489bc22ede944ccf4ecbab4cc618ef3ed01eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac000247304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee0121025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee635711000000";
+
+    #[test]
+    fn compact_size_boundaries() {
+        for (value, expected) in [
+            (0u64, "00"),
+            (0xfc, "fc"),
+            (0xfd, "fdfd00"),
+            (0xffff, "fdffff"),
+            (0x1_0000, "fe00000100"),
+            (0xffff_ffff, "feffffffff"),
+            (0x1_0000_0000, "ff0000000001000000"),
+        ] {
+            let mut data = Vec::new();
+            write_compact_size(&mut data, value);
+            assert_eq!(hex::encode(&data), expected);
+
+            let mut reader = Reader {
+                data: &data,
+                position: 0,
+            };
+            assert_eq!(reader.read_compact_size().unwrap(), value);
+        }
+
+        let mut reader = Reader {
+            data: &[0xfd, 0xfc, 0x00],
+            position: 0,
+        };
+        assert!(reader.read_compact_size().is_err());
+    }
+
+    #[test]
+    fn legacy_round_trip_and_txid() {
+        let raw = hex::decode(GENESIS_COINBASE).unwrap();
+        let tx = deserialize_transaction(&raw).unwrap();
+
+        assert_eq!(tx.inputs.len(), 1);
+        assert_eq!(tx.inputs[0].script_sig.len(), 0x4d);
+        assert_eq!(tx.outputs[0].value, 5_000_000_000);
+        assert_eq!(serialize_transaction(&tx).unwrap(), raw);
+        assert_eq!(
+            txid(&tx).unwrap(),
+            "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"
+        );
+        assert_eq!(wtxid(&tx).unwrap(), txid(&tx).unwrap());
+    }
+
+    #[test]
+    fn segwit_round_trip_and_ids() {
+        let raw = hex::decode(BIP143_P2WPKH).unwrap();
+        let tx = deserialize_transaction(&raw).unwrap();
+
+        assert!(tx.inputs[0].witness.is_empty());
+        assert_eq!(tx.inputs[1].witness.len(), 2);
+        assert_eq!(tx.inputs[1].witness[1].len(), 33);
+        assert_eq!(tx.locktime, 17);
+        assert_eq!(serialize_transaction(&tx).unwrap(), raw);
+        assert_eq!(
+            txid(&tx).unwrap(),
+            "e8151a2af31c368a35053ddd4bdb285a8595c769a3ad83e0fa02314a602d4609"
+        );
+        assert_eq!(
+            wtxid(&tx).unwrap(),
+            "c36c38370907df2324d9ce9d149d191192f338b37665a82e78e76a12c909b762"
+        );
+
+        let stripped = serialize_transaction_without_witness(&tx).unwrap();
+        assert_eq!(stripped.len(), raw.len() - 2 - 1 - 0x6b);
+    }
+
+    #[test]
+    fn large_scripts_use_multi_byte_lengths() {
+        let tx = BitcoinTransaction {
+            version: 2,
+            inputs: vec![BitcoinInput {
+                txid: "11".repeat(32),
+                vout: 0,
+                script_sig: vec![0x51; 300],
+                sequence: 0xffff_fffd,
+                witness: Vec::new(),
+            }],
+            outputs: vec![BitcoinOutput {
+                value: 1,
+                script_pubkey: vec![0x6a],
+            }],
+            locktime: 0,
+        };
+        let raw = serialize_transaction(&tx).unwrap();
+
+        assert_eq!(&raw[4 + 1 + 36..4 + 1 + 36 + 3], &[0xfd, 0x2c, 0x01]);
+        assert_eq!(deserialize_transaction(&raw).unwrap(), tx);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let mut tx = deserialize_transaction(&hex::decode(GENESIS_COINBASE).unwrap()).unwrap();
+        tx.inputs[0].txid = "zz".repeat(32);
+        assert!(serialize_transaction(&tx).is_err());
+        tx.inputs[0].txid = "00".repeat(31);
+        assert!(txid(&tx).is_err());
+
+        let mut raw = hex::decode(GENESIS_COINBASE).unwrap();
+        raw.push(0);
+        assert!(deserialize_transaction(&raw).is_err());
+        assert!(deserialize_transaction(&raw[..raw.len() - 10]).is_err());
+    }
+
+    #[test]
+    fn bip143_p2wpkh_sighash_and_witness() {
+        let mut tx = deserialize_transaction(&hex::decode(BIP143_P2WPKH).unwrap()).unwrap();
+        let expected_witness = tx.inputs[1].witness.clone();
+        for input in tx.inputs.iter_mut() {
+            input.script_sig.clear();
+            input.witness.clear();
+        }
+
+        let pubkey_hash: [u8; 20] = hex::decode("1d0f172a0ecb48aee1be1f2687d2963ae33f71a1")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let sighash = p2wpkh_sighash(&tx, 1, &pubkey_hash, 600_000_000).unwrap();
+        assert_eq!(
+            hex::encode(sighash),
+            "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670"
+        );
+
+        let r: [u8; 32] =
+            hex::decode("3609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let low_s: [u8; 32] =
+            hex::decode("573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let high_s = sub_be(&SECP256K1_ORDER, &low_s);
+        let witness = p2wpkh_witness(&r, &high_s, SIGHASH_ALL, &expected_witness[1]).unwrap();
+        assert_eq!(witness, expected_witness);
+    }
+
+    #[test]
+    fn der_pads_high_bit_and_strips_leading_zeros() {
+        let mut r = [0u8; 32];
+        r[1] = 0x80;
+        let s = [0x01; 32];
+        let signature = der_encode_signature(&r, &s);
+
+        assert_eq!(&signature[..4], &[0x30, 0x44, 0x02, 0x20]);
+        assert_eq!(&signature[4..6], &[0x00, 0x80]);
+        assert_eq!(&signature[36..38], &[0x02, 0x20]);
+    }
+
+    #[test]
+    fn mpc_big_r_to_r() {
+        let point = format!("03{}", "ab".repeat(32));
+        assert_eq!(mpc_signature_r(&point).unwrap(), [0xab; 32]);
+        assert!(mpc_signature_r(&format!("04{}", "ab".repeat(32))).is_err());
+    }
+
+    // BIP-341 keyPathSpending vector: rawUnsignedTx and the scriptPubKey and
+    // amount of each utxosSpent.
+    const BIP341_UNSIGNED_TX: &str = "02000000097de20cbff686da83a54981d2b9bab3586f4ca7e48f57f5b55963115f3b334e9c010000000000000000d7b7cab57b1393ace2d064f4d4a2cb8af6def61273e127517d44759b6dafdd990000000000fffffffff8e1f583384333689228c5d28eac13366be082dc57441760d957275419a418420000000000fffffffff0689180aa63b30cb162a73c6d2a38b7eeda2a83ece74310fda0843ad604853b0100000000feffffffaa5202bdf6d8ccd2ee0f0202afbbb7461d9264a25e5bfd3c5a52ee1239e0ba6c0000000000feffffff956149bdc66faa968eb2be2d2faa29718acbfe3941215893a2a3446d32acd050000000000000000000e664b9773b88c09c32cb70a2a3e4da0ced63b7ba3b22f848531bbb1d5d5f4c94010000000000000000e9aa6b8e6c9de67619e6a3924ae25696bb7b694bb677a632a74ef7eadfd4eabf0000000000ffffffffa778eb6a263dc090464cd125c466b5a99667720b1c110468831d058aa1b82af10100000000ffffffff0200ca9a3b000000001976a91406afd46bcdfd22ef94ac122aa11f241244a37ecc88ac807840cb0000000020ac9a87f5594be208f8532db38cff670c450ed2fea8fcdefcc9a663f78bab962b0065cd1d";
+    const BIP341_UTXOS_SPENT: [(&str, u64); 9] = [
+        (
+            "512053a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343",
+            420_000_000,
+        ),
+        (
+            "5120147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3",
+            462_000_000,
+        ),
+        (
+            "76a914751e76e8199196d454941c45d1b3a323f1433bd688ac",
+            294_000_000,
+        ),
+        (
+            "5120e4d810fd50586274face62b8a807eb9719cef49c04177cc6b76a9a4251d5450e",
+            504_000_000,
+        ),
+        (
+            "512091b64d5324723a985170e4dc5a0f84c041804f2cd12660fa5dec09fc21783605",
+            630_000_000,
+        ),
+        ("00147dd65592d0ab2fe0d0257d571abf032cd9db93dc", 378_000_000),
+        (
+            "512075169f4001aa68f15bbed28b218df1d0a62cbbcf1188c6665110c293c907b831",
+            672_000_000,
+        ),
+        (
+            "5120712447206d7a5238acc7ff53fbe94a3b64539ad291c7cdbc490b7577e4b17df5",
+            546_000_000,
+        ),
+        (
+            "512077e30a5522dd9f894c3f8b8bd4c4b2cf82ca7da8a3ea6a239655c39c050ab220",
+            588_000_000,
+        ),
+    ];
+
+    fn bip341_spend() -> (BitcoinTransaction, Vec<BitcoinOutput>) {
+        let tx = deserialize_transaction(&hex::decode(BIP341_UNSIGNED_TX).unwrap()).unwrap();
+        let prevouts = BIP341_UTXOS_SPENT
+            .iter()
+            .map(|(script_pubkey, value)| BitcoinOutput {
+                value: *value,
+                script_pubkey: hex::decode(script_pubkey).unwrap(),
+            })
+            .collect();
+        (tx, prevouts)
+    }
+
+    #[test]
+    fn bip341_key_path_sig_msgs() {
+        let (tx, prevouts) = bip341_spend();
+        // nVersion, nLockTime, sha_prevouts, sha_amounts, sha_scriptpubkeys and
+        // sha_sequences, common to every input that does not use ANYONECANPAY.
+        let committed = "020000000065cd1de3b33bb4ef3a52ad1fffb555c0d82828eb22737036eaeb02a235d82b909c4c3f58a6964a4f5f8f0b642ded0a8a553be7622a719da71d1f5befcefcdee8e0fde623ad0f61ad2bca5ba6a7693f50fce988e17c3780bf2b1e720cfbb38fbdd52e2118959c7221ab5ce9e26c3cd67b22c24f8baa54bac281d8e6b05e400e6c3a957e";
+
+        for (input_index, sighash_type, expected) in [
+            (
+                0,
+                SIGHASH_SINGLE,
+                format!("0003{}0000000000d0418f0e9a36245b9a50ec87f8bf5be5bcae434337b87139c3a5b1f56e33cba0", committed),
+            ),
+            (
+                1,
+                SIGHASH_SINGLE | SIGHASH_ANYONECANPAY,
+                "0083020000000065cd1d00d7b7cab57b1393ace2d064f4d4a2cb8af6def61273e127517d44759b6dafdd9900000000808f891b00000000225120147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3ffffffffffcef8fb4ca7efc5433f591ecfc57391811ce1e186a3793024def5c884cba51d".to_string(),
+            ),
+            (
+                3,
+                SIGHASH_ALL,
+                format!("0001{}a2e6dab7c1f0dcd297c8d61647fd17d821541ea69c3cc37dcbad7f90d4eb4bc50003000000", committed),
+            ),
+            (
+                4,
+                SIGHASH_DEFAULT,
+                format!("0000{}a2e6dab7c1f0dcd297c8d61647fd17d821541ea69c3cc37dcbad7f90d4eb4bc50004000000", committed),
+            ),
+            (
+                8,
+                SIGHASH_ALL | SIGHASH_ANYONECANPAY,
+                "0081020000000065cd1da2e6dab7c1f0dcd297c8d61647fd17d821541ea69c3cc37dcbad7f90d4eb4bc500a778eb6a263dc090464cd125c466b5a99667720b1c110468831d058aa1b82af101000000002b0c230000000022512077e30a5522dd9f894c3f8b8bd4c4b2cf82ca7da8a3ea6a239655c39c050ab220ffffffff".to_string(),
+            ),
+        ] {
+            let message =
+                taproot_key_path_sig_msg(&tx, input_index, &prevouts, sighash_type).unwrap();
+            assert_eq!(hex::encode(message), expected, "input {}", input_index);
+        }
+    }
+
+    #[test]
+    fn bip341_key_path_sighashes() {
+        let (tx, prevouts) = bip341_spend();
+
+        for (input_index, sighash_type, expected) in [
+            (
+                0,
+                SIGHASH_SINGLE,
+                "2514a6272f85cfa0f45eb907fcb0d121b808ed37c6ea160a5a9046ed5526d555",
+            ),
+            (
+                1,
+                SIGHASH_SINGLE | SIGHASH_ANYONECANPAY,
+                "325a644af47e8a5a2591cda0ab0723978537318f10e6a63d4eed783b96a71a4d",
+            ),
+            (
+                3,
+                SIGHASH_ALL,
+                "bf013ea93474aa67815b1b6cc441d23b64fa310911d991e713cd34c7f5d46669",
+            ),
+            (
+                4,
+                SIGHASH_DEFAULT,
+                "4f900a0bae3f1446fd48490c2958b5a023228f01661cda3496a11da502a7f7ef",
+            ),
+            (
+                6,
+                SIGHASH_NONE,
+                "15f25c298eb5cdc7eb1d638dd2d45c97c4c59dcaec6679cfc16ad84f30876b85",
+            ),
+            (
+                7,
+                SIGHASH_NONE | SIGHASH_ANYONECANPAY,
+                "cd292de50313804dabe4685e83f923d2969577191a3e1d2882220dca88cbeb10",
+            ),
+            (
+                8,
+                SIGHASH_ALL | SIGHASH_ANYONECANPAY,
+                "cccb739eca6c13a8a89e6e5cd317ffe55669bbda23f2fd37b0f18755e008edd2",
+            ),
+        ] {
+            let sighash =
+                taproot_key_path_sighash(&tx, input_index, &prevouts, sighash_type).unwrap();
+            assert_eq!(hex::encode(sighash), expected, "input {}", input_index);
+        }
+
+        assert!(taproot_key_path_sighash(&tx, 0, &prevouts[..1], SIGHASH_DEFAULT).is_err());
+        assert!(taproot_key_path_sighash(&tx, 0, &prevouts, 0x04).is_err());
+        assert!(taproot_key_path_sighash(&tx, 9, &prevouts, SIGHASH_DEFAULT).is_err());
+        assert_eq!(
+            taproot_key_path_witness(&[7; 64], SIGHASH_DEFAULT)[0].len(),
+            64
+        );
+        assert_eq!(
+            taproot_key_path_witness(&[7; 64], SIGHASH_ALL)[0][64],
+            SIGHASH_ALL
+        );
+    }
+
+    #[test]
+    fn parses_base58_addresses() {
+        let cases = [
+            (
+                "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa",
+                BitcoinNetwork::Mainnet,
+                "76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac",
+            ),
+            (
+                "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+                BitcoinNetwork::Mainnet,
+                "a914b472a266d0bd89c13706a4132ccfb16f7c3b9fcb87",
+            ),
+            (
+                "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn",
+                BitcoinNetwork::Testnet,
+                "76a914243f1394f44554f4ce3fd68649c19adc483ce92488ac",
+            ),
+            (
+                "2MzQwSSnBHWHqSAqtTVQ6v47XtaisrJa1Vc",
+                BitcoinNetwork::Signet,
+                "a9144e9f39ca4688ff102128ea4ccda34105324305b087",
+            ),
+        ];
+        for (address, network, script_pubkey) in cases {
+            let parsed = parse_address(address, network).unwrap();
+            assert_eq!(hex::encode(parsed.script_pubkey()), script_pubkey);
+        }
+
+        assert!(parse_address(
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa",
+            BitcoinNetwork::Testnet
+        )
+        .is_err());
+        assert!(parse_address(
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNb",
+            BitcoinNetwork::Mainnet
+        )
+        .is_err());
+        assert!(parse_address(
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7Divf0a",
+            BitcoinNetwork::Mainnet
+        )
+        .is_err());
+    }
+
+    // BIP-173 and BIP-350 test vectors.
+    #[test]
+    fn parses_segwit_addresses() {
+        let cases = [
+            (
+                "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4",
+                BitcoinNetwork::Mainnet,
+                "0014751e76e8199196d454941c45d1b3a323f1433bd6",
+            ),
+            (
+                "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+                BitcoinNetwork::Testnet,
+                "00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262",
+            ),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+                BitcoinNetwork::Mainnet,
+                "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            ),
+            (
+                "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c",
+                BitcoinNetwork::Signet,
+                "5120000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433",
+            ),
+        ];
+        for (address, network, script_pubkey) in cases {
+            let parsed = parse_address(address, network).unwrap();
+            assert_eq!(hex::encode(parsed.script_pubkey()), script_pubkey);
+        }
+
+        // Wrong network, mixed case, bad checksum.
+        assert!(parse_address(
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            BitcoinNetwork::Testnet
+        )
+        .is_err());
+        assert!(parse_address(
+            "bc1qW508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            BitcoinNetwork::Mainnet
+        )
+        .is_err());
+        assert!(parse_address(
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5",
+            BitcoinNetwork::Mainnet
+        )
+        .is_err());
+        // Taproot program encoded with a bech32 (not bech32m) checksum.
+        assert!(parse_address(
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+            BitcoinNetwork::Mainnet
+        )
+        .is_err());
+        // Valid bech32m, but a 40-byte v1 program is not a Taproot output.
+        assert_eq!(
+            parse_address(
+                "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y",
+                BitcoinNetwork::Mainnet
+            )
+            .unwrap_err(),
+            "invalid witness v1 program length"
+        );
+    }
+}
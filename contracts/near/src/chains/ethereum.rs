@@ -1,6 +1,10 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::env;
 use near_sdk::serde::{Deserialize, Serialize};
 
+use super::secp256k1::{sub_be, SECP256K1_HALF_ORDER, SECP256K1_ORDER};
+
+/// Legacy transaction, signed per EIP-155.
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct EthTransaction {
@@ -15,26 +19,140 @@ pub struct EthTransaction {
     pub s: U256,
 }
 
+/// EIP-1559 (type 2) transaction. `y_parity`, `r` and `s` are zero until signed.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Eip1559Transaction {
+    pub chain_id: u64,
+    pub nonce: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: U256,
+    pub to: Option<String>,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub access_list: Vec<AccessListItem>,
+    pub y_parity: u8,
+    pub r: U256,
+    pub s: U256,
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
+pub struct AccessListItem {
+    pub address: String,
+    pub storage_keys: Vec<U256>,
+}
+
+/// EIP-2718 type byte for EIP-1559 transactions.
+pub const EIP1559_TX_TYPE: u8 = 0x02;
+
+/// `bytes4(keccak256("transfer(address,uint256)"))`.
+pub const ERC20_TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+
+/// Chain, nonce and fee fields for a type-2 payout transaction.
+#[derive(Clone, Debug)]
+pub struct Eip1559Params {
+    pub chain_id: u64,
+    pub nonce: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: U256,
+}
+
+/// Big-endian 256-bit integer.
+#[derive(
+    BorshDeserialize,
+    BorshSerialize,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Debug,
+    Default,
+)]
+#[serde(crate = "near_sdk::serde")]
 pub struct U256(pub [u8; 32]);
 
+impl U256 {
+    pub fn from_u128(value: u128) -> Self {
+        let mut bytes = [0u8; 32];
+        bytes[16..].copy_from_slice(&value.to_be_bytes());
+        U256(bytes)
+    }
+
+    pub fn from_u64(value: u64) -> Self {
+        Self::from_u128(value as u128)
+    }
+
+    /// Parses up to 64 hex digits, with or without a `0x` prefix.
+    pub fn from_hex(value: &str) -> Option<Self> {
+        let digits = value.trim().trim_start_matches("0x");
+        if digits.is_empty() || digits.len() > 64 {
+            return None;
+        }
+        let padded = format!("{:0>64}", digits);
+        let decoded = hex::decode(padded).ok()?;
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&decoded);
+        Some(U256(bytes))
+    }
+
+    /// The value without leading zero bytes; empty for zero.
+    pub fn to_min_bytes(&self) -> &[u8] {
+        let first = self.0.iter().position(|byte| *byte != 0).unwrap_or(32);
+        &self.0[first..]
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().all(|byte| *byte == 0)
+    }
+}
+
 pub fn rlp_encode(input: &[u8]) -> Vec<u8> {
     if input.len() == 1 && input[0] < 0x80 {
-        return vec![input[0]];
+        vec![input[0]]
     } else if input.len() <= 55 {
         let mut result = vec![0x80 + input.len() as u8];
         result.extend_from_slice(input);
-        return result;
+        result
     } else {
         let len_bytes = to_min_bytes(input.len());
         let mut result = vec![0xb7 + len_bytes.len() as u8];
         result.extend_from_slice(&len_bytes);
         result.extend_from_slice(input);
-        return result;
+        result
     }
 }
 
+/// Encodes a list whose items are already RLP-encoded.
+pub fn rlp_encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload_len: usize = items.iter().map(Vec::len).sum();
+    let mut result = if payload_len <= 55 {
+        vec![0xc0 + payload_len as u8]
+    } else {
+        let len_bytes = to_min_bytes(payload_len);
+        let mut header = vec![0xf7 + len_bytes.len() as u8];
+        header.extend_from_slice(&len_bytes);
+        header
+    };
+    for item in items {
+        result.extend_from_slice(item);
+    }
+    result
+}
+
+/// Encodes an integer as a byte string without leading zeros (zero is `0x80`).
+pub fn rlp_encode_scalar(value: &U256) -> Vec<u8> {
+    rlp_encode(value.to_min_bytes())
+}
+
+pub fn rlp_encode_u64(value: u64) -> Vec<u8> {
+    rlp_encode_scalar(&U256::from_u64(value))
+}
+
 pub fn to_min_bytes(val: usize) -> Vec<u8> {
     let mut bytes = Vec::new();
     let mut v = val;
@@ -49,10 +167,513 @@ pub fn to_min_bytes(val: usize) -> Vec<u8> {
     bytes
 }
 
-// Basic implementation for ETH transaction RLP encoding
-// Note: This is simplified and assumes legacy transaction type for MVP
+/// Decodes a `0x`-prefixed 20-byte address.
+pub fn parse_address(value: &str) -> Option<[u8; 20]> {
+    let digits = value.trim().strip_prefix("0x")?;
+    let decoded = hex::decode(digits).ok()?;
+    decoded.try_into().ok()
+}
+
+fn encode_to(to: &Option<String>) -> Vec<u8> {
+    let to = match to {
+        Some(address) => parse_address(address)
+            .unwrap_or_else(|| env::panic_str("invalid ethereum address"))
+            .to_vec(),
+        None => Vec::new(),
+    };
+    rlp_encode(&to)
+}
+
+fn encode_fields(tx: &EthTransaction, v: &U256, r: &U256, s: &U256) -> Vec<u8> {
+    rlp_encode_list(&[
+        rlp_encode_scalar(&tx.nonce),
+        rlp_encode_scalar(&tx.gas_price),
+        rlp_encode_scalar(&tx.gas_limit),
+        encode_to(&tx.to),
+        rlp_encode_scalar(&tx.value),
+        rlp_encode(&tx.data),
+        rlp_encode_scalar(v),
+        rlp_encode_scalar(r),
+        rlp_encode_scalar(s),
+    ])
+}
+
+/// EIP-155 signing payload: the legacy fields followed by `chain_id, 0, 0`.
+pub fn signing_payload(tx: &EthTransaction, chain_id: u64) -> Vec<u8> {
+    encode_fields(
+        tx,
+        &U256::from_u64(chain_id),
+        &U256::default(),
+        &U256::default(),
+    )
+}
+
+/// Keccak-256 of the EIP-155 signing payload; the digest the MPC signer signs.
+pub fn signing_hash(tx: &EthTransaction, chain_id: u64) -> [u8; 32] {
+    env::keccak256_array(&signing_payload(tx, chain_id))
+}
+
+/// Fills `v`, `r` and `s` from an MPC signature over `signing_hash`. A high
+/// `s` is normalized to `n - s` with the recovery id flipped.
+pub fn apply_signature(
+    tx: &EthTransaction,
+    chain_id: u64,
+    r: U256,
+    s: U256,
+    recovery_id: u8,
+) -> EthTransaction {
+    let (s, recovery_id) = normalize_signature(s, recovery_id);
+
+    let mut signed = tx.clone();
+    signed.v = chain_id * 2 + 35 + recovery_id as u64;
+    signed.r = r;
+    signed.s = s;
+    signed
+}
+
+/// Legacy (EIP-155) transaction as broadcast: RLP of all nine fields.
 pub fn encode_transaction(tx: &EthTransaction) -> Vec<u8> {
-    // Implementation would go here to encode the full tx structure
-    // nonce, gas_price, gas_limit, to, value, data, v, r, s
-    vec![]
+    encode_fields(tx, &U256::from_u64(tx.v), &tx.r, &tx.s)
+}
+
+fn normalize_signature(s: U256, recovery_id: u8) -> (U256, u8) {
+    assert!(recovery_id <= 1, "recovery_id must be 0 or 1");
+    if s.0 > SECP256K1_HALF_ORDER {
+        (U256(sub_be(&SECP256K1_ORDER, &s.0)), recovery_id ^ 1)
+    } else {
+        (s, recovery_id)
+    }
+}
+
+fn encode_access_list(access_list: &[AccessListItem]) -> Vec<u8> {
+    let items: Vec<Vec<u8>> = access_list
+        .iter()
+        .map(|item| {
+            let address = parse_address(&item.address)
+                .unwrap_or_else(|| env::panic_str("invalid ethereum address"));
+            let storage_keys: Vec<Vec<u8>> = item
+                .storage_keys
+                .iter()
+                .map(|key| rlp_encode(&key.0))
+                .collect();
+            rlp_encode_list(&[rlp_encode(&address), rlp_encode_list(&storage_keys)])
+        })
+        .collect();
+    rlp_encode_list(&items)
+}
+
+fn eip1559_fields(tx: &Eip1559Transaction) -> Vec<Vec<u8>> {
+    vec![
+        rlp_encode_u64(tx.chain_id),
+        rlp_encode_scalar(&tx.nonce),
+        rlp_encode_scalar(&tx.max_priority_fee_per_gas),
+        rlp_encode_scalar(&tx.max_fee_per_gas),
+        rlp_encode_scalar(&tx.gas_limit),
+        encode_to(&tx.to),
+        rlp_encode_scalar(&tx.value),
+        rlp_encode(&tx.data),
+        encode_access_list(&tx.access_list),
+    ]
+}
+
+fn typed_envelope(tx_type: u8, fields: &[Vec<u8>]) -> Vec<u8> {
+    let mut envelope = vec![tx_type];
+    envelope.extend(rlp_encode_list(fields));
+    envelope
+}
+
+/// `0x02 || rlp([chain_id, nonce, ..., data, access_list])`.
+pub fn eip1559_signing_payload(tx: &Eip1559Transaction) -> Vec<u8> {
+    typed_envelope(EIP1559_TX_TYPE, &eip1559_fields(tx))
+}
+
+pub fn eip1559_signing_hash(tx: &Eip1559Transaction) -> [u8; 32] {
+    env::keccak256_array(&eip1559_signing_payload(tx))
+}
+
+/// Fills `y_parity`, `r` and `s` from an MPC signature over `eip1559_signing_hash`.
+pub fn apply_eip1559_signature(
+    tx: &Eip1559Transaction,
+    r: U256,
+    s: U256,
+    recovery_id: u8,
+) -> Eip1559Transaction {
+    let (s, y_parity) = normalize_signature(s, recovery_id);
+
+    let mut signed = tx.clone();
+    signed.y_parity = y_parity;
+    signed.r = r;
+    signed.s = s;
+    signed
+}
+
+/// Signed type-2 transaction as broadcast: `0x02 || rlp([..., y_parity, r, s])`.
+pub fn encode_eip1559_transaction(tx: &Eip1559Transaction) -> Vec<u8> {
+    let mut fields = eip1559_fields(tx);
+    fields.push(rlp_encode_u64(tx.y_parity as u64));
+    fields.push(rlp_encode_scalar(&tx.r));
+    fields.push(rlp_encode_scalar(&tx.s));
+    typed_envelope(EIP1559_TX_TYPE, &fields)
+}
+
+/// ABI-encoded calldata for `transfer(address,uint256)`.
+pub fn encode_erc20_transfer(recipient: &[u8; 20], amount: &U256) -> Vec<u8> {
+    let mut data = Vec::with_capacity(4 + 32 + 32);
+    data.extend_from_slice(&ERC20_TRANSFER_SELECTOR);
+    data.extend_from_slice(&[0u8; 12]);
+    data.extend_from_slice(recipient);
+    data.extend_from_slice(&amount.0);
+    data
+}
+
+/// Unsigned payout of `amount` base units to `recipient`: an ERC-20 `transfer`
+/// call when `token_contract` is set, a plain value transfer otherwise.
+pub fn build_payout_transaction(
+    params: &Eip1559Params,
+    token_contract: Option<&str>,
+    recipient: &str,
+    amount: U256,
+) -> Eip1559Transaction {
+    let recipient_address = parse_address(recipient)
+        .unwrap_or_else(|| env::panic_str("invalid ethereum recipient address"));
+    let (to, value, data) = match token_contract {
+        Some(token_contract) => (
+            token_contract.to_string(),
+            U256::default(),
+            encode_erc20_transfer(&recipient_address, &amount),
+        ),
+        None => (recipient.trim().to_string(), amount, Vec::new()),
+    };
+
+    Eip1559Transaction {
+        chain_id: params.chain_id,
+        nonce: params.nonce,
+        max_priority_fee_per_gas: params.max_priority_fee_per_gas,
+        max_fee_per_gas: params.max_fee_per_gas,
+        gas_limit: params.gas_limit,
+        to: Some(to),
+        value,
+        data,
+        access_list: Vec::new(),
+        y_parity: 0,
+        r: U256::default(),
+        s: U256::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unhex(value: &str) -> Vec<u8> {
+        hex::decode(value.trim_start_matches("0x")).unwrap()
+    }
+
+    /// The example transaction from the EIP-155 specification.
+    fn eip155_example() -> EthTransaction {
+        EthTransaction {
+            nonce: U256::from_u64(9),
+            gas_price: U256::from_u128(20_000_000_000),
+            gas_limit: U256::from_u64(21_000),
+            to: Some("0x3535353535353535353535353535353535353535".to_string()),
+            value: U256::from_u128(1_000_000_000_000_000_000),
+            data: Vec::new(),
+            v: 0,
+            r: U256::default(),
+            s: U256::default(),
+        }
+    }
+
+    #[test]
+    fn rlp_matches_spec_examples() {
+        assert_eq!(rlp_encode(b"dog"), unhex("83646f67"));
+        assert_eq!(rlp_encode(b""), unhex("80"));
+        assert_eq!(rlp_encode_list(&[]), unhex("c0"));
+        assert_eq!(
+            rlp_encode_list(&[rlp_encode(b"cat"), rlp_encode(b"dog")]),
+            unhex("c88363617483646f67")
+        );
+        assert_eq!(rlp_encode_u64(0), unhex("80"));
+        assert_eq!(rlp_encode_u64(15), unhex("0f"));
+        assert_eq!(rlp_encode_u64(1024), unhex("820400"));
+        assert_eq!(
+            rlp_encode(b"Lorem ipsum dolor sit amet, consectetur adipisicing elit"),
+            [
+                unhex("b838"),
+                b"Lorem ipsum dolor sit amet, consectetur adipisicing elit".to_vec()
+            ]
+            .concat()
+        );
+        // [ [], [[]], [ [], [[]] ] ]
+        let empty = rlp_encode_list(&[]);
+        let nested = rlp_encode_list(std::slice::from_ref(&empty));
+        assert_eq!(
+            rlp_encode_list(&[
+                empty.clone(),
+                nested.clone(),
+                rlp_encode_list(&[empty, nested])
+            ]),
+            unhex("c7c0c1c0c3c0c1c0")
+        );
+    }
+
+    #[test]
+    fn long_list_uses_length_of_length() {
+        let items: Vec<Vec<u8>> = (0..60).map(|_| rlp_encode_u64(1)).collect();
+        let encoded = rlp_encode_list(&items);
+        assert_eq!(&encoded[..2], &[0xf8, 60]);
+        assert_eq!(encoded.len(), 62);
+    }
+
+    #[test]
+    fn eip155_signing_payload_and_hash() {
+        let tx = eip155_example();
+        assert_eq!(
+            signing_payload(&tx, 1),
+            unhex("ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080")
+        );
+        assert_eq!(
+            signing_hash(&tx, 1).to_vec(),
+            unhex("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53")
+        );
+    }
+
+    #[test]
+    fn eip155_signed_transaction() {
+        let r = U256::from_hex("28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276")
+            .unwrap();
+        let s = U256::from_hex("67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83")
+            .unwrap();
+        let signed = apply_signature(&eip155_example(), 1, r, s, 0);
+
+        assert_eq!(signed.v, 37);
+        assert_eq!(
+            encode_transaction(&signed),
+            unhex("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83")
+        );
+    }
+
+    /// Mainnet transaction 0x88df0164…944b: 0.00429 ETH with `hello!` as calldata.
+    #[test]
+    fn mainnet_legacy_transaction() {
+        let unsigned = EthTransaction {
+            nonce: U256::from_u64(0x15),
+            gas_price: U256::from_u128(0x4a817c800),
+            gas_limit: U256::from_u64(0xc350),
+            to: Some("0xf02c1c8e6114b1dbe8937a39260b5b0a374432bb".to_string()),
+            value: U256::from_u128(0xf3dbb76162000),
+            data: b"hello!".to_vec(),
+            v: 0,
+            r: U256::default(),
+            s: U256::default(),
+        };
+        let r = U256::from_hex("1b5e176d927f8e9ab405058b2d2457392da3e20f328b16ddabcebc33eaac5fea")
+            .unwrap();
+        let s = U256::from_hex("4ba69724e8f69de52f0125ad8b3c5c2cef33019bac3249e2c0a2192766d1721c")
+            .unwrap();
+        let signed = apply_signature(&unsigned, 1, r, s, 0);
+        let raw = encode_transaction(&signed);
+
+        assert_eq!(signed.v, 0x25);
+        assert_eq!(
+            raw,
+            unhex("f871158504a817c80082c35094f02c1c8e6114b1dbe8937a39260b5b0a374432bb870f3dbb761620008668656c6c6f2125a01b5e176d927f8e9ab405058b2d2457392da3e20f328b16ddabcebc33eaac5feaa04ba69724e8f69de52f0125ad8b3c5c2cef33019bac3249e2c0a2192766d1721c")
+        );
+        assert_eq!(
+            env::keccak256_array(&raw).to_vec(),
+            unhex("88df016429689c079f3b2f6ad39fa052532c56795b733da78a91ebe6a713944b")
+        );
+    }
+
+    #[test]
+    fn high_s_is_normalized() {
+        let r = U256::from_u64(1);
+        let low_s =
+            U256::from_hex("67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83")
+                .unwrap();
+        let high_s = U256(sub_be(&SECP256K1_ORDER, &low_s.0));
+
+        let signed = apply_signature(&eip155_example(), 1, r, high_s, 1);
+        assert_eq!(signed.s, low_s);
+        assert_eq!(signed.v, 37);
+    }
+
+    #[test]
+    fn contract_creation_encodes_empty_to() {
+        let mut tx = eip155_example();
+        tx.to = None;
+        let payload = signing_payload(&tx, 1);
+        // nonce, gas price, gas limit, then an empty `to` string
+        assert_eq!(&payload[1..12], &unhex("098504a817c80082520880")[..]);
+    }
+
+    fn eip1559_example() -> Eip1559Transaction {
+        Eip1559Transaction {
+            chain_id: 1,
+            nonce: U256::default(),
+            max_priority_fee_per_gas: U256::from_u128(1_000_000_000),
+            max_fee_per_gas: U256::from_u128(2_000_000_000),
+            gas_limit: U256::from_u64(21_000),
+            to: Some("0x3535353535353535353535353535353535353535".to_string()),
+            value: U256::from_u128(1_000_000_000_000_000_000),
+            data: Vec::new(),
+            access_list: Vec::new(),
+            y_parity: 0,
+            r: U256::default(),
+            s: U256::default(),
+        }
+    }
+
+    #[test]
+    fn eip1559_signing_payload_layout() {
+        let payload = eip1559_signing_payload(&eip1559_example());
+        assert_eq!(
+            payload,
+            unhex("02ef0180843b9aca008477359400825208943535353535353535353535353535353535353535880de0b6b3a764000080c0")
+        );
+        assert_eq!(
+            eip1559_signing_hash(&eip1559_example()),
+            env::keccak256_array(&payload)
+        );
+        assert_ne!(
+            eip1559_signing_hash(&eip1559_example()),
+            env::keccak256_array(&payload[1..])
+        );
+    }
+
+    #[test]
+    fn eip1559_signed_envelope() {
+        let r = U256::from_hex("28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276")
+            .unwrap();
+        let s = U256::from_hex("67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83")
+            .unwrap();
+        let signed = apply_eip1559_signature(&eip1559_example(), r, s, 1);
+
+        assert_eq!(signed.y_parity, 1);
+        assert_eq!(
+            encode_eip1559_transaction(&signed),
+            unhex("02f8720180843b9aca008477359400825208943535353535353535353535353535353535353535880de0b6b3a764000080c001a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83")
+        );
+    }
+
+    /// Mainnet type-2 transaction 0xce4dc6d7…ab31 from 0x001e2b7d…efce: a
+    /// contract call with a four-byte selector and no access list.
+    #[test]
+    fn mainnet_eip1559_transaction() {
+        let unsigned = Eip1559Transaction {
+            chain_id: 1,
+            nonce: U256::from_u64(2),
+            max_priority_fee_per_gas: U256::from_u128(0x3b9aca00),
+            max_fee_per_gas: U256::from_u128(0x029e7822d6),
+            gas_limit: U256::from_u64(0x98f0),
+            to: Some("0xd9e1459a7a482635700cbc20bbaf52d495ab9c96".to_string()),
+            value: U256::default(),
+            data: unhex("1b55ba3a"),
+            access_list: Vec::new(),
+            y_parity: 0,
+            r: U256::default(),
+            s: U256::default(),
+        };
+        let r = U256::from_hex("c199674fcb29f353693dd779c017823b954b3c69dffa3cd6b2a6ff7888798039")
+            .unwrap();
+        let s = U256::from_hex("28ca912de909e7e6cdef9cdcaf24c54dd8c1032946dfa1d85c206b32a9064fe8")
+            .unwrap();
+        let signed = apply_eip1559_signature(&unsigned, r, s, 0);
+        let raw = encode_eip1559_transaction(&signed);
+
+        assert_eq!(
+            raw,
+            unhex("02f86f0102843b9aca0085029e7822d68298f094d9e1459a7a482635700cbc20bbaf52d495ab9c9680841b55ba3ac080a0c199674fcb29f353693dd779c017823b954b3c69dffa3cd6b2a6ff7888798039a028ca912de909e7e6cdef9cdcaf24c54dd8c1032946dfa1d85c206b32a9064fe8")
+        );
+        assert_eq!(
+            env::keccak256_array(&raw).to_vec(),
+            unhex("ce4dc6d7a7549a98ee3b071b67e970879ff51b5b95d1c340bacd80fa1e1aab31")
+        );
+
+        // (r, s, y_parity = 0) over this digest recovers the sender 0x001e2b7d…efce.
+        assert_eq!(
+            eip1559_signing_hash(&unsigned).to_vec(),
+            unhex("fb09c91c47c2717aadedeae95931a545a3d0d183c8c584325af02db1a85bfae8")
+        );
+    }
+
+    #[test]
+    fn eip1559_access_list_encoding() {
+        let mut tx = eip1559_example();
+        tx.access_list = vec![AccessListItem {
+            address: "0x3535353535353535353535353535353535353535".to_string(),
+            storage_keys: vec![U256::from_u64(1)],
+        }];
+        let payload = eip1559_signing_payload(&tx);
+        let access_list = unhex(
+            "f838f7943535353535353535353535353535353535353535e1a00000000000000000000000000000000000000000000000000000000000000001",
+        );
+        assert!(payload.ends_with(&access_list));
+        // 46 bytes of fields before the access list, then the 58-byte access list
+        assert_eq!(&payload[..3], &[EIP1559_TX_TYPE, 0xf8, 46 + 58]);
+    }
+
+    fn payout_params() -> Eip1559Params {
+        Eip1559Params {
+            chain_id: 1,
+            nonce: U256::from_u64(7),
+            max_priority_fee_per_gas: U256::from_u128(1_000_000_000),
+            max_fee_per_gas: U256::from_u128(30_000_000_000),
+            gas_limit: U256::from_u64(65_000),
+        }
+    }
+
+    #[test]
+    fn erc20_transfer_calldata() {
+        let recipient = parse_address("0x3535353535353535353535353535353535353535").unwrap();
+        assert_eq!(
+            encode_erc20_transfer(&recipient, &U256::from_u64(1_000_000)),
+            unhex(
+                "a9059cbb\
+                 0000000000000000000000003535353535353535353535353535353535353535\
+                 00000000000000000000000000000000000000000000000000000000000f4240"
+            )
+        );
+    }
+
+    #[test]
+    fn erc20_payout_calls_token_contract() {
+        let usdc = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+        let tx = build_payout_transaction(
+            &payout_params(),
+            Some(usdc),
+            "0x3535353535353535353535353535353535353535",
+            U256::from_u64(1_000_000),
+        );
+
+        assert_eq!(tx.to.as_deref(), Some(usdc));
+        assert!(tx.value.is_zero());
+        assert_eq!(&tx.data[..4], &ERC20_TRANSFER_SELECTOR);
+        assert_eq!(tx.data.len(), 68);
+    }
+
+    #[test]
+    fn native_payout_sends_value() {
+        let tx = build_payout_transaction(
+            &payout_params(),
+            None,
+            "0x3535353535353535353535353535353535353535",
+            U256::from_u64(42),
+        );
+
+        assert_eq!(
+            tx.to.as_deref(),
+            Some("0x3535353535353535353535353535353535353535")
+        );
+        assert_eq!(tx.value, U256::from_u64(42));
+        assert!(tx.data.is_empty());
+    }
+
+    #[test]
+    fn u256_hex_round_trip() {
+        assert_eq!(U256::from_hex("0x0400").unwrap(), U256::from_u64(1024));
+        assert_eq!(U256::from_u64(1024).to_min_bytes(), &[0x04, 0x00]);
+        assert!(U256::default().to_min_bytes().is_empty());
+        assert!(U256::from_hex(&"f".repeat(65)).is_none());
+    }
 }
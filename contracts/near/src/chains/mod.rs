@@ -1,2 +1,3 @@
 pub mod bitcoin;
 pub mod ethereum;
+pub mod secp256k1;
@@ -0,0 +1,50 @@
+/// secp256k1 group order divided by two; signatures with a larger `s` are
+/// rejected by Ethereum (EIP-2) and Bitcoin (BIP-146) and must be flipped to `n - s`.
+pub(crate) const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+pub(crate) const SECP256K1_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// Big-endian `a - b`; callers ensure `a >= b`.
+pub(crate) fn sub_be(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    let mut borrow = 0i16;
+    for index in (0..32).rev() {
+        let mut diff = a[index] as i16 - b[index] as i16 - borrow;
+        borrow = if diff < 0 {
+            diff += 256;
+            1
+        } else {
+            0
+        };
+        out[index] = diff as u8;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_minus_half_order_is_one_above_it() {
+        let mut above_half = SECP256K1_HALF_ORDER;
+        above_half[31] += 1;
+        assert_eq!(sub_be(&SECP256K1_ORDER, &SECP256K1_HALF_ORDER), above_half);
+    }
+
+    #[test]
+    fn sub_be_borrows_across_bytes() {
+        let mut a = [0u8; 32];
+        a[30] = 1;
+        let mut b = [0u8; 32];
+        b[31] = 1;
+        let mut expected = [0u8; 32];
+        expected[31] = 0xff;
+        assert_eq!(sub_be(&a, &b), expected);
+    }
+}
@@ -1,22 +1,44 @@
+#![allow(clippy::too_many_arguments)]
+
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LookupMap, UnorderedSet};
 use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{env, near_bindgen, AccountId, PanicOnDefault, Promise};
+use near_sdk::{env, near_bindgen, AccountId, NearToken, PanicOnDefault, Promise, PromiseOrValue};
+
+use anypay_attestation_types::{
+    decode_hex_fixed, payee_details_preimage, verify_signature, AttestationRecord,
+    ATTESTATION_VERSION_V1, ATTESTATION_VERSION_V2,
+};
 
 #[cfg(feature = "abi")]
 use near_sdk::schemars::JsonSchema;
 
 pub mod chains;
 
+use chains::bitcoin::{self, BitcoinNetwork};
+use chains::ethereum;
+
 // MPC Contract ID (on Testnet)
 const MPC_CONTRACT_ID: &str = "v1.signer-prod.testnet";
 
 const DEFAULT_V2_STORAGE_FEE_YOCTO: u128 = 50_000_000_000_000_000_000_000; // 0.05 NEAR
 const DEFAULT_TOPUP_WINDOW_MS: u64 = 10_800_000; // 3 hours
 const DEFAULT_MAX_QUOTE_ROTATIONS: u16 = 48;
+const DEFAULT_PRICE_MAX_STALENESS_MS: u64 = 300_000; // 5 minutes
+const MAX_PRICE_SPREAD_BPS: i32 = 5_000;
+const INTENT_STORAGE_DEPOSIT_YOCTO: u128 = 10_000_000_000_000_000_000_000; // 0.01 NEAR
+const MAX_BUYER_ALLOWLIST_LEN: usize = 500;
+const MAX_DISPUTE_EVIDENCE: usize = 16;
+const MAX_DISPUTE_REASON_LEN: usize = 512;
+const DEFAULT_INTENT_RETENTION_MS: u64 = 2_592_000_000; // 30 days
 const MAX_VIEW_LIMIT: usize = 200;
-const ATTESTATION_VERSION: &str = "anypay/tlsn-attestation/v1";
+const MAX_INTENT_SCAN: u64 = 1_000;
+const MAX_ORDER_BOOK_SCAN: usize = 500;
+const MAX_INTENT_GROUP_LEGS: usize = 10;
+const PRECISE_UNIT: u128 = 1_000_000_000_000_000_000;
+const BPS_DENOMINATOR: u128 = 10_000;
+const MAX_ATTESTATION_AMOUNT_TOLERANCE_BPS: u16 = 1_000;
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
@@ -64,6 +86,97 @@ pub struct Contract {
     pub used_attestation_sessions: LookupMap<String, bool>,
     pub intent_attestations: LookupMap<String, String>,
     pub attestation_public_key_hex: String,
+    pub attestation_amount_tolerance_bps: u16,
+
+    // === PRICING STATE ===
+    // Mapping: DepositId -> per-payment-method data, parallel to Deposit.payment_methods
+    pub deposit_payment_method_data: LookupMap<u64, Vec<DepositPaymentMethodData>>,
+    // Mapping: IntentHash -> conversion rate locked at signal time
+    pub intent_conversion_rates: LookupMap<String, u128>,
+    // Mapping: DepositId -> currencies priced off an oracle feed
+    pub deposit_price_pegs: LookupMap<u64, Vec<DepositPricePeg>>,
+    // Mapping: FeedId -> latest price pushed by the oracle
+    pub price_feeds: LookupMap<String, PriceFeed>,
+    pub price_max_staleness_ms: u64,
+
+    // Mapping: PaymentMethod name -> TLS server names accepted in attestations
+    pub payment_method_server_names: LookupMap<String, Vec<String>>,
+    // Enumerable index of registered payment method names
+    pub payment_method_names: UnorderedSet<String>,
+
+    // Mapping: IntentHash -> buyer key and seller-encrypted payee details
+    pub intent_payee_reveals: LookupMap<String, PayeeDetailsReveal>,
+
+    // === BUYER LIMITS ===
+    // Zero disables the per-buyer cap and the cancel cooldown
+    pub max_intents_per_buyer: u16,
+    pub intent_cancel_cooldown_ms: u64,
+    pub min_intent_collateral_yocto: u128,
+    // Mapping: Buyer -> signaled or pending intents not yet closed (including disputed ones)
+    pub buyer_open_intent_counts: LookupMap<AccountId, u16>,
+    // Mapping: "buyer:deposit_id" -> time until which the buyer cannot signal on the deposit
+    pub buyer_deposit_cooldowns: LookupMap<String, u64>,
+    // Mapping: IntentHash -> yoctoNEAR collateral attached by the buyer beyond
+    // INTENT_STORAGE_DEPOSIT_YOCTO, held until the intent closes or its held payout settles
+    pub intent_collateral: LookupMap<String, u128>,
+
+    // === BUYER POLICIES ===
+    // Mapping: DepositId -> who may signal intents (absent means Open)
+    pub deposit_buyer_policies: LookupMap<u64, BuyerPolicy>,
+    // Mapping: DepositId -> buyers pre-approved by the seller
+    pub deposit_buyer_allowlists: LookupMap<u64, UnorderedSet<AccountId>>,
+
+    // === DISPUTE STATE ===
+    // Resolves disputes; the owner acts as arbiter when unset
+    pub arbiter_account_id: Option<AccountId>,
+    // Zero pays out on fulfillment; otherwise payouts wait for claim_intent_payout
+    pub dispute_window_ms: u64,
+    // Mapping: IntentHash -> dispute record
+    pub intent_disputes: LookupMap<String, IntentDispute>,
+    // Mapping: IntentHash -> time after which a held fulfillment payout may be claimed
+    pub intent_payout_release_at_ms: LookupMap<String, u64>,
+    // Mapping: IntentHash -> fulfillment time, kept while the payout is held
+    pub intent_fulfilled_at_ms: LookupMap<String, u64>,
+
+    // === REPUTATION ===
+    pub account_stats: LookupMap<AccountId, AccountStats>,
+    // Mapping: DepositId -> fulfilled buyer intents required to signal
+    pub deposit_min_buyer_fulfilled_intents: LookupMap<u64, u64>,
+
+    // === INTENT HOUSEKEEPING ===
+    // Mapping: DepositId -> intents holding a slot (signaled or disputed; pending approval
+    // intents take one once approved);
+    // deposit_intents keeps the full history until it is garbage-collected
+    pub deposit_active_intents: LookupMap<u64, UnorderedSet<String>>,
+    // Deposit ids created before active intents were tracked, still to be rebuilt;
+    // they cannot take intents until next passes them
+    pub active_intents_backfill_next: u64,
+    pub active_intents_backfill_last: u64,
+    pub intent_retention_ms: u64,
+    // Mapping: IntentHash -> when the intent became terminal; retention runs from here
+    pub intent_closed_at_ms: LookupMap<String, u64>,
+
+    // Mapping: FundingStatus -> V2 deposits currently in that status
+    pub deposits_by_funding_status: LookupMap<FundingStatus, UnorderedSet<u64>>,
+    // Deposit ids created before the index existed, still to be backfilled;
+    // the index is complete once next passes last
+    pub funding_status_backfill_next: u64,
+    pub funding_status_backfill_last: u64,
+
+    // === INTENT GROUPS ===
+    pub intent_group_counter: u64,
+    // Mapping: GroupId -> intents signaled together by signal_intent_batch
+    pub intent_groups: LookupMap<String, IntentGroup>,
+    // Mapping: IntentHash -> GroupId of the group the intent was signaled in
+    pub intent_group_ids: LookupMap<String, String>,
+
+    // === EVM PAYOUTS ===
+    // Mapping: deposit token (asset id) -> EVM chain and contract used for payouts
+    pub evm_tokens: LookupMap<String, EvmToken>,
+    pub evm_token_ids: UnorderedSet<String>,
+
+    // Network `recipient` must belong to for intents paid out on Bitcoin
+    pub bitcoin_network: BitcoinNetwork,
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
@@ -106,6 +219,32 @@ pub struct V2Contract {
     pub max_quote_rotations: u16,
 }
 
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct V3Contract {
+    pub owner_id: AccountId,
+    pub deposit_counter: u64,
+    pub deposits: LookupMap<u64, Deposit>,
+    pub account_deposits: LookupMap<AccountId, UnorderedSet<u64>>,
+    pub deposit_intents: LookupMap<u64, UnorderedSet<String>>,
+    pub intent_counter: u64,
+    pub intents: LookupMap<String, Intent>,
+    pub account_intents: LookupMap<AccountId, UnorderedSet<String>>,
+    pub payment_methods: LookupMap<String, PaymentMethod>,
+    pub protocol_fee: u128,
+    pub protocol_fee_recipient: AccountId,
+    pub max_intents_per_deposit: u8,
+    pub intent_expiration_period: u64,
+    pub deposit_funding: LookupMap<u64, DepositFundingMeta>,
+    pub open_deposits_by_asset: LookupMap<String, UnorderedSet<u64>>,
+    pub oracle_account_id: AccountId,
+    pub v2_storage_fee_yocto: u128,
+    pub topup_window_ms: u64,
+    pub max_quote_rotations: u16,
+    pub used_attestation_sessions: LookupMap<String, bool>,
+    pub intent_attestations: LookupMap<String, String>,
+    pub attestation_public_key_hex: String,
+}
+
 // === STRUCTS ===
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
@@ -125,21 +264,65 @@ pub struct Deposit {
     pub payment_methods: Vec<String>, // List of payment method names
 }
 
+/// Seller terms for one entry of `Deposit.payment_methods`, stored at the same index.
+/// A non-empty `payee_details_hash` means the entry is a bare `platform` and the
+/// handle is kept off-chain: it is the hex sha256 of `payee_details_preimage`.
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
 #[serde(crate = "near_sdk::serde")]
 pub struct DepositPaymentMethodData {
+    #[serde(default)]
     pub payee_details_hash: String,
+    #[serde(default)]
     pub verification_data: String,
     pub currencies: Vec<Currency>,
 }
 
+/// Payee details for an intent on a hashed payment method. The buyer supplies
+/// `buyer_encryption_public_key` when signaling; the seller (or delegate)
+/// answers with the handle encrypted to that key.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct PayeeDetailsReveal {
+    pub buyer_encryption_public_key: String,
+    pub encrypted_payee_details: Option<String>,
+    pub revealed_at_ms: u64,
+}
+
+/// A fiat currency accepted for a deposit. `min_conversion_rate` is the
+/// amount of fiat minor units owed per token base unit, scaled by 1e18
+/// (e.g. 1 USDC with 6 decimals at 1.00 USD is `100 / 1e6 * 1e18 = 1e14`).
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
 #[serde(crate = "near_sdk::serde")]
 pub struct Currency {
     pub code: String,
     pub min_conversion_rate: u128,
 }
 
+/// Floating price for one currency of a deposit: the feed rate adjusted by
+/// `spread_bps` (e.g. `-100` for "spot minus 1%"). The currency's
+/// `min_conversion_rate` still applies as a floor.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct DepositPricePeg {
+    pub currency_code: String,
+    pub feed_id: String,
+    pub spread_bps: i32,
+}
+
+/// Oracle price in the same units as `Currency.min_conversion_rate`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceFeed {
+    pub rate: u128,
+    pub observed_at_ms: u64,
+    pub updated_at_ms: u64,
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
 #[cfg_attr(feature = "abi", derive(JsonSchema))]
 #[serde(crate = "near_sdk::serde")]
@@ -178,6 +361,10 @@ pub struct IntentTransferDetailsView {
     pub platform: String,
     pub tagname: String,
     pub memo: String,
+    pub conversion_rate: Option<u128>,
+    pub fiat_amount_due: Option<String>,
+    pub payee_details_hash: Option<String>,
+    pub encrypted_payee_details: Option<String>,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
@@ -188,6 +375,115 @@ pub enum IntentStatus {
     Fulfilled,
     Cancelled,
     Released,
+    Expired,
+    PendingApproval,
+    Rejected,
+    Disputed,
+}
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub enum DisputeOutcome {
+    Fulfill,
+    Cancel,
+}
+
+/// A contested intent. `status_before` is `Signaled`, or `Fulfilled` when the
+/// dispute was opened against a payout still held in the dispute window.
+/// Evidence is referenced by sha256 hex; the documents themselves live off-chain.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct IntentDispute {
+    pub opened_by: AccountId,
+    pub opened_at_ms: u64,
+    pub status_before: IntentStatus,
+    pub evidence_hashes: Vec<String>,
+    pub outcome: Option<DisputeOutcome>,
+    pub resolved_by: Option<AccountId>,
+    pub resolved_at_ms: u64,
+    pub reason: String,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenVolume {
+    pub token: String,
+    pub amount: u128,
+}
+
+/// Intent counters for one side of an account's trades. Time to fulfill is
+/// measured from signal to fulfillment.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct IntentStats {
+    pub signaled: u64,
+    pub fulfilled: u64,
+    pub cancelled: u64,
+    pub expired: u64,
+    pub disputed: u64,
+    pub volume: Vec<TokenVolume>,
+    pub total_time_to_fulfill_ms: u64,
+    pub average_time_to_fulfill_ms: u64,
+}
+
+impl IntentStats {
+    fn record_fulfillment(&mut self, token: &str, amount: u128, time_to_fulfill_ms: u64) {
+        self.fulfilled += 1;
+        self.total_time_to_fulfill_ms += time_to_fulfill_ms;
+        match self.volume.iter_mut().find(|volume| volume.token == token) {
+            Some(volume) => volume.amount += amount,
+            None => self.volume.push(TokenVolume {
+                token: token.to_string(),
+                amount,
+            }),
+        }
+        self.refresh_average();
+    }
+
+    fn revert_fulfillment(&mut self, token: &str, amount: u128, time_to_fulfill_ms: u64) {
+        self.fulfilled = self.fulfilled.saturating_sub(1);
+        self.total_time_to_fulfill_ms = self
+            .total_time_to_fulfill_ms
+            .saturating_sub(time_to_fulfill_ms);
+        if let Some(volume) = self.volume.iter_mut().find(|volume| volume.token == token) {
+            volume.amount = volume.amount.saturating_sub(amount);
+        }
+        self.refresh_average();
+    }
+
+    fn refresh_average(&mut self) {
+        self.average_time_to_fulfill_ms = self
+            .total_time_to_fulfill_ms
+            .checked_div(self.fulfilled)
+            .unwrap_or(0);
+    }
+}
+
+/// Reputation counters for an account, as a buyer and as a seller.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountStats {
+    pub buyer: IntentStats,
+    pub seller: IntentStats,
+}
+
+/// Which buyers may signal intents on a deposit. Under `Allowlist` only
+/// allowlisted buyers may signal; under `ManualApproval` other buyers get a
+/// `PendingApproval` intent that locks no liquidity until the seller accepts it.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub enum BuyerPolicy {
+    Open,
+    Allowlist,
+    ManualApproval,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
@@ -222,6 +518,35 @@ pub struct DepositFundingMeta {
     pub updated_at_ms: u64,
 }
 
+/// One payment method of an open deposit quoting a currency. `conversion_rate`
+/// is the effective rate after any price peg; `fiat_amount_due` is set when
+/// the query named an amount.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct OrderBookEntry {
+    pub deposit_id: u64,
+    pub depositor: AccountId,
+    pub payment_method: String,
+    pub platform: String,
+    pub currency_code: String,
+    pub conversion_rate: u128,
+    pub min_intent_amount: u128,
+    pub max_intent_amount: u128,
+    pub available_amount: u128,
+    pub fiat_amount_due: Option<String>,
+}
+
+/// Quotes from one page of open deposits, cheapest first. `next_index` is the
+/// `from_index` of the next page of deposits, or `None` once all were scanned.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct OrderBookPage {
+    pub entries: Vec<OrderBookEntry>,
+    pub next_index: Option<u64>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[cfg_attr(feature = "abi", derive(JsonSchema))]
 #[serde(crate = "near_sdk::serde")]
@@ -254,44 +579,110 @@ pub struct V2Config {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[cfg_attr(feature = "abi", derive(JsonSchema))]
 #[serde(crate = "near_sdk::serde")]
-pub struct AttestationChecksPayload {
-    pub recv_body_revealed: bool,
-    pub memo_match: Option<bool>,
-    pub amount_match: Option<bool>,
-    pub currency_match: Option<bool>,
-    pub platform_match: Option<bool>,
-    pub tagname_match: Option<bool>,
-    pub policy_passed: bool,
+pub struct IntentLimitsConfig {
+    pub max_intents_per_buyer: u16,
+    pub intent_cancel_cooldown_ms: u64,
+    pub min_intent_collateral_yocto: U128,
+    pub intent_expiration_period: u64,
+}
+
+/// EVM payout asset for a deposit `token`. `contract_address` is the ERC-20
+/// contract, or `None` for the chain's native coin. `decimals` is the token's
+/// precision on the EVM chain and `deposit_decimals` the precision of deposit
+/// and intent amounts; payouts are rescaled between the two.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct EvmToken {
+    pub asset_id: String,
+    pub chain_id: u64,
+    pub contract_address: Option<String>,
+    pub decimals: u8,
+    pub deposit_decimals: u8,
+    pub symbol: String,
+}
+
+/// Unsigned EIP-1559 payout for an intent, ready for the MPC signer. `amount`
+/// is the intent amount; `token_amount` is what is sent, in the token's EVM
+/// decimals.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct EvmPayoutView {
+    pub intent_hash: String,
+    pub asset_id: String,
+    pub chain_id: u64,
+    pub token_contract: Option<String>,
+    pub recipient: String,
+    pub amount: U128,
+    pub token_amount: U128,
+    pub signing_payload_hex: String,
+    pub signing_hash_hex: String,
+}
+
+/// One deposit's share of a `signal_intent_batch` order.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct IntentLeg {
+    pub deposit_id: u64,
+    pub amount: U128,
+    pub payment_method: String,
+}
+
+/// Intents signaled together by `signal_intent_batch`. Each leg is an
+/// ordinary intent and is fulfilled on its own.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct IntentGroup {
+    pub group_id: String,
+    pub buyer: AccountId,
+    pub intent_hashes: Vec<String>,
+    pub created_at_ms: u64,
+}
+
+/// Filter for intent record views. Every set field must match; `platform`
+/// matches the part of the payment method before `::`, and the time range
+/// applies to the intent timestamp in milliseconds (`to_ms` exclusive).
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct IntentFilter {
+    pub statuses: Option<Vec<IntentStatus>>,
+    pub chain: Option<String>,
+    pub platform: Option<String>,
+    pub from_ms: Option<u64>,
+    pub to_ms: Option<u64>,
+}
+
+/// Result of one `gc_deposit_intents` page. `next_index` is the `from_index`
+/// to pass for the next page, already adjusted for removed intents, or `None`
+/// once the history has been scanned to the end.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "abi", derive(JsonSchema))]
+#[serde(crate = "near_sdk::serde")]
+pub struct IntentCollection {
+    pub removed: u64,
+    pub next_index: Option<u64>,
 }
 
+/// A page of intent records. `next_index` is the `from_index` to pass for the
+/// next page, or `None` once the set has been scanned to the end.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[cfg_attr(feature = "abi", derive(JsonSchema))]
 #[serde(crate = "near_sdk::serde")]
-pub struct AttestationSignaturePayload {
-    pub algorithm: String,
-    pub public_key_hex: String,
-    pub signature_hex: String,
+pub struct IntentPage {
+    pub intents: Vec<Intent>,
+    pub next_index: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[cfg_attr(feature = "abi", derive(JsonSchema))]
 #[serde(crate = "near_sdk::serde")]
-pub struct AttestationPayload {
-    pub attestation_id: String,
-    pub version: String,
-    pub session_id: String,
-    pub intent_id: Option<String>,
-    pub server_name: String,
-    pub expected_memo: Option<String>,
-    pub expected_amount: Option<String>,
-    pub expected_currency: Option<String>,
-    pub expected_platform: Option<String>,
-    pub expected_tagname: Option<String>,
-    pub transcript_digest_sha256: String,
-    pub issued_at_ms: u64,
-    pub expires_at_ms: u64,
-    pub checks: AttestationChecksPayload,
-    pub signature: AttestationSignaturePayload,
+pub struct DisputeConfig {
+    pub arbiter_account_id: AccountId,
+    pub dispute_window_ms: u64,
 }
 
 // === IMPLEMENTATION ===
@@ -323,6 +714,44 @@ impl Contract {
             used_attestation_sessions: LookupMap::new(b"s"),
             intent_attestations: LookupMap::new(b"t"),
             attestation_public_key_hex: String::new(),
+            attestation_amount_tolerance_bps: 0,
+            deposit_payment_method_data: LookupMap::new(b"c"),
+            intent_conversion_rates: LookupMap::new(b"r"),
+            deposit_price_pegs: LookupMap::new(b"g"),
+            price_feeds: LookupMap::new(b"q"),
+            price_max_staleness_ms: DEFAULT_PRICE_MAX_STALENESS_MS,
+            payment_method_server_names: LookupMap::new(b"n"),
+            payment_method_names: UnorderedSet::new(b"m"),
+            intent_payee_reveals: LookupMap::new(b"x"),
+            max_intents_per_buyer: 0,
+            intent_cancel_cooldown_ms: 0,
+            min_intent_collateral_yocto: 0,
+            buyer_open_intent_counts: LookupMap::new(b"h"),
+            buyer_deposit_cooldowns: LookupMap::new(b"k"),
+            intent_collateral: LookupMap::new(b"l"),
+            deposit_buyer_policies: LookupMap::new(b"j"),
+            deposit_buyer_allowlists: LookupMap::new(b"v"),
+            arbiter_account_id: None,
+            dispute_window_ms: 0,
+            intent_disputes: LookupMap::new(b"y"),
+            intent_payout_release_at_ms: LookupMap::new(b"z"),
+            intent_fulfilled_at_ms: LookupMap::new(b"F"),
+            account_stats: LookupMap::new(b"S"),
+            deposit_min_buyer_fulfilled_intents: LookupMap::new(b"R"),
+            deposit_active_intents: LookupMap::new(b"D"),
+            intent_retention_ms: DEFAULT_INTENT_RETENTION_MS,
+            intent_closed_at_ms: LookupMap::new(b"C"),
+            deposits_by_funding_status: LookupMap::new(b"I"),
+            funding_status_backfill_next: 1,
+            funding_status_backfill_last: 0,
+            active_intents_backfill_next: 1,
+            active_intents_backfill_last: 0,
+            intent_group_counter: 0,
+            intent_groups: LookupMap::new(b"G"),
+            intent_group_ids: LookupMap::new(b"H"),
+            evm_tokens: LookupMap::new(b"E"),
+            evm_token_ids: UnorderedSet::new(b"T"),
+            bitcoin_network: BitcoinNetwork::Testnet,
         }
     }
 
@@ -362,6 +791,44 @@ impl Contract {
             used_attestation_sessions: LookupMap::new(b"s"),
             intent_attestations: LookupMap::new(b"t"),
             attestation_public_key_hex: String::new(),
+            attestation_amount_tolerance_bps: 0,
+            deposit_payment_method_data: LookupMap::new(b"c"),
+            intent_conversion_rates: LookupMap::new(b"r"),
+            deposit_price_pegs: LookupMap::new(b"g"),
+            price_feeds: LookupMap::new(b"q"),
+            price_max_staleness_ms: DEFAULT_PRICE_MAX_STALENESS_MS,
+            payment_method_server_names: LookupMap::new(b"n"),
+            payment_method_names: UnorderedSet::new(b"m"),
+            intent_payee_reveals: LookupMap::new(b"x"),
+            max_intents_per_buyer: 0,
+            intent_cancel_cooldown_ms: 0,
+            min_intent_collateral_yocto: 0,
+            buyer_open_intent_counts: LookupMap::new(b"h"),
+            buyer_deposit_cooldowns: LookupMap::new(b"k"),
+            intent_collateral: LookupMap::new(b"l"),
+            deposit_buyer_policies: LookupMap::new(b"j"),
+            deposit_buyer_allowlists: LookupMap::new(b"v"),
+            arbiter_account_id: None,
+            dispute_window_ms: 0,
+            intent_disputes: LookupMap::new(b"y"),
+            intent_payout_release_at_ms: LookupMap::new(b"z"),
+            intent_fulfilled_at_ms: LookupMap::new(b"F"),
+            account_stats: LookupMap::new(b"S"),
+            deposit_min_buyer_fulfilled_intents: LookupMap::new(b"R"),
+            deposit_active_intents: LookupMap::new(b"D"),
+            intent_retention_ms: DEFAULT_INTENT_RETENTION_MS,
+            intent_closed_at_ms: LookupMap::new(b"C"),
+            deposits_by_funding_status: LookupMap::new(b"I"),
+            funding_status_backfill_next: 1,
+            funding_status_backfill_last: old.deposit_counter,
+            active_intents_backfill_next: 1,
+            active_intents_backfill_last: old.deposit_counter,
+            intent_group_counter: 0,
+            intent_groups: LookupMap::new(b"G"),
+            intent_group_ids: LookupMap::new(b"H"),
+            evm_tokens: LookupMap::new(b"E"),
+            evm_token_ids: UnorderedSet::new(b"T"),
+            bitcoin_network: BitcoinNetwork::Testnet,
         }
     }
 
@@ -427,6 +894,44 @@ impl Contract {
                 used_attestation_sessions: LookupMap::new(b"s"),
                 intent_attestations: LookupMap::new(b"t"),
                 attestation_public_key_hex: configured_attestation_key.unwrap_or_default(),
+                attestation_amount_tolerance_bps: 0,
+                deposit_payment_method_data: LookupMap::new(b"c"),
+                intent_conversion_rates: LookupMap::new(b"r"),
+                deposit_price_pegs: LookupMap::new(b"g"),
+                price_feeds: LookupMap::new(b"q"),
+                price_max_staleness_ms: DEFAULT_PRICE_MAX_STALENESS_MS,
+                payment_method_server_names: LookupMap::new(b"n"),
+                payment_method_names: UnorderedSet::new(b"m"),
+                intent_payee_reveals: LookupMap::new(b"x"),
+                max_intents_per_buyer: 0,
+                intent_cancel_cooldown_ms: 0,
+                min_intent_collateral_yocto: 0,
+                buyer_open_intent_counts: LookupMap::new(b"h"),
+                buyer_deposit_cooldowns: LookupMap::new(b"k"),
+                intent_collateral: LookupMap::new(b"l"),
+                deposit_buyer_policies: LookupMap::new(b"j"),
+                deposit_buyer_allowlists: LookupMap::new(b"v"),
+                arbiter_account_id: None,
+                dispute_window_ms: 0,
+                intent_disputes: LookupMap::new(b"y"),
+                intent_payout_release_at_ms: LookupMap::new(b"z"),
+                intent_fulfilled_at_ms: LookupMap::new(b"F"),
+                account_stats: LookupMap::new(b"S"),
+                deposit_min_buyer_fulfilled_intents: LookupMap::new(b"R"),
+                deposit_active_intents: LookupMap::new(b"D"),
+                intent_retention_ms: DEFAULT_INTENT_RETENTION_MS,
+                intent_closed_at_ms: LookupMap::new(b"C"),
+                deposits_by_funding_status: LookupMap::new(b"I"),
+                funding_status_backfill_next: 1,
+                funding_status_backfill_last: previous.deposit_counter,
+                active_intents_backfill_next: 1,
+                active_intents_backfill_last: previous.deposit_counter,
+                intent_group_counter: 0,
+                intent_groups: LookupMap::new(b"G"),
+                intent_group_ids: LookupMap::new(b"H"),
+                evm_tokens: LookupMap::new(b"E"),
+                evm_token_ids: UnorderedSet::new(b"T"),
+                bitcoin_network: BitcoinNetwork::Testnet,
             };
         }
 
@@ -457,12 +962,73 @@ impl Contract {
                 used_attestation_sessions: LookupMap::new(b"s"),
                 intent_attestations: LookupMap::new(b"t"),
                 attestation_public_key_hex: configured_attestation_key.unwrap_or_default(),
+                attestation_amount_tolerance_bps: 0,
+                deposit_payment_method_data: LookupMap::new(b"c"),
+                intent_conversion_rates: LookupMap::new(b"r"),
+                deposit_price_pegs: LookupMap::new(b"g"),
+                price_feeds: LookupMap::new(b"q"),
+                price_max_staleness_ms: DEFAULT_PRICE_MAX_STALENESS_MS,
+                payment_method_server_names: LookupMap::new(b"n"),
+                payment_method_names: UnorderedSet::new(b"m"),
+                intent_payee_reveals: LookupMap::new(b"x"),
+                max_intents_per_buyer: 0,
+                intent_cancel_cooldown_ms: 0,
+                min_intent_collateral_yocto: 0,
+                buyer_open_intent_counts: LookupMap::new(b"h"),
+                buyer_deposit_cooldowns: LookupMap::new(b"k"),
+                intent_collateral: LookupMap::new(b"l"),
+                deposit_buyer_policies: LookupMap::new(b"j"),
+                deposit_buyer_allowlists: LookupMap::new(b"v"),
+                arbiter_account_id: None,
+                dispute_window_ms: 0,
+                intent_disputes: LookupMap::new(b"y"),
+                intent_payout_release_at_ms: LookupMap::new(b"z"),
+                intent_fulfilled_at_ms: LookupMap::new(b"F"),
+                account_stats: LookupMap::new(b"S"),
+                deposit_min_buyer_fulfilled_intents: LookupMap::new(b"R"),
+                deposit_active_intents: LookupMap::new(b"D"),
+                intent_retention_ms: DEFAULT_INTENT_RETENTION_MS,
+                intent_closed_at_ms: LookupMap::new(b"C"),
+                deposits_by_funding_status: LookupMap::new(b"I"),
+                funding_status_backfill_next: 1,
+                funding_status_backfill_last: previous.deposit_counter,
+                active_intents_backfill_next: 1,
+                active_intents_backfill_last: previous.deposit_counter,
+                intent_group_counter: 0,
+                intent_groups: LookupMap::new(b"G"),
+                intent_group_ids: LookupMap::new(b"H"),
+                evm_tokens: LookupMap::new(b"E"),
+                evm_token_ids: UnorderedSet::new(b"T"),
+                bitcoin_network: BitcoinNetwork::Testnet,
             };
         }
 
         env::panic_str("Unsupported contract state for migrate_v3");
     }
 
+    /// Migrates V3 state. `payment_method_names` lists payment methods
+    /// registered before the enumerable registry index existed.
+    #[init(ignore_state)]
+    pub fn migrate_v4(payment_method_names: Option<Vec<String>>) -> Self {
+        let state_bytes = env::storage_read(b"STATE").expect("Old state does not exist");
+        let caller = env::predecessor_account_id();
+
+        let mut migrated = Self::migrate_v4_state(&state_bytes);
+        assert_eq!(caller, migrated.owner_id, "Owner only");
+
+        for name in payment_method_names.unwrap_or_default() {
+            if let Some(mut payment_method) = migrated.payment_methods.get(&name) {
+                let key = Self::payment_method_key(&name);
+                payment_method.name = key.clone();
+                migrated.payment_methods.remove(&name);
+                migrated.payment_methods.insert(&key, &payment_method);
+                migrated.payment_method_names.insert(&key);
+            }
+        }
+
+        migrated
+    }
+
     // === ESCROW FUNCTIONS (V1) ===
 
     #[payable]
@@ -474,6 +1040,7 @@ impl Contract {
         max_intent_amount: U128,
         payment_methods: Vec<String>,
         delegate: Option<AccountId>,
+        payment_method_data: Option<Vec<DepositPaymentMethodData>>,
     ) -> u64 {
         let amount = amount.0;
         let min_intent_amount = min_intent_amount.0;
@@ -490,10 +1057,10 @@ impl Contract {
             !payment_methods.is_empty(),
             "At least one payment method required"
         );
-        assert!(
-            Self::extract_payment_details(&payment_methods).is_some(),
-            "payment_methods must include platform::tagname"
-        );
+        let payment_method_data =
+            Self::normalize_payment_method_data(&payment_methods, payment_method_data);
+        Self::assert_payee_details(&payment_methods, payment_method_data.as_deref());
+        self.assert_payment_methods_registered(&payment_methods, payment_method_data.as_deref());
 
         self.deposit_counter += 1;
         let deposit_id = self.deposit_counter;
@@ -518,6 +1085,10 @@ impl Contract {
             &deposit_id,
             &UnorderedSet::new(format!("di:{}", deposit_id).as_bytes()),
         );
+        if let Some(payment_method_data) = payment_method_data {
+            self.deposit_payment_method_data
+                .insert(&deposit_id, &payment_method_data);
+        }
 
         env::log_str(&format!("Deposit created: {}", deposit_id));
         deposit_id
@@ -562,6 +1133,199 @@ impl Contract {
         ));
     }
 
+    /// Updates pricing and intent limits for future intents. Existing intents
+    /// keep the conversion rate locked when they were signaled. Price pegs on
+    /// currencies dropped from `currency_rates` are removed.
+    pub fn update_deposit_terms(
+        &mut self,
+        deposit_id: u64,
+        min_intent_amount: Option<U128>,
+        max_intent_amount: Option<U128>,
+        currency_rates: Option<Vec<Vec<Currency>>>,
+    ) {
+        let caller = env::predecessor_account_id();
+        let mut deposit = self.deposits.get(&deposit_id).expect("Deposit not found");
+        assert!(self.is_deposit_manager(&caller, &deposit), "Unauthorized");
+
+        let min_intent_amount = min_intent_amount.map_or(deposit.min_intent_amount, |v| v.0);
+        let max_intent_amount = max_intent_amount.map_or(deposit.max_intent_amount, |v| v.0);
+        assert!(
+            min_intent_amount > 0,
+            "Min intent amount must be greater than 0"
+        );
+        assert!(min_intent_amount <= max_intent_amount, "Min must be <= max");
+        // A V2 deposit still awaiting funding is checked against its expected amount.
+        let available = match self.deposit_funding.get(&deposit_id) {
+            Some(funding) if funding.status == FundingStatus::AwaitingFunding => {
+                deposit.total_deposit
+            }
+            _ => deposit.remaining_deposits,
+        };
+        assert!(
+            min_intent_amount <= available,
+            "Min intent amount exceeds remaining deposits"
+        );
+        deposit.min_intent_amount = min_intent_amount;
+        deposit.max_intent_amount = max_intent_amount;
+
+        if let Some(currency_rates) = currency_rates {
+            assert_eq!(
+                currency_rates.len(),
+                deposit.payment_methods.len(),
+                "currency_rates must match payment_methods"
+            );
+            let existing = self
+                .deposit_payment_method_data
+                .get(&deposit_id)
+                .unwrap_or_default();
+            let payment_method_data = currency_rates
+                .into_iter()
+                .enumerate()
+                .map(|(index, currencies)| {
+                    let previous = existing.get(index);
+                    DepositPaymentMethodData {
+                        payee_details_hash: previous
+                            .map(|data| data.payee_details_hash.clone())
+                            .unwrap_or_default(),
+                        verification_data: previous
+                            .map(|data| data.verification_data.clone())
+                            .unwrap_or_default(),
+                        currencies,
+                    }
+                })
+                .collect();
+            let payment_method_data = Self::normalize_payment_method_data(
+                &deposit.payment_methods,
+                Some(payment_method_data),
+            )
+            .unwrap();
+            self.assert_payment_methods_registered(
+                &deposit.payment_methods,
+                Some(&payment_method_data),
+            );
+            self.retain_quoted_price_pegs(deposit_id, &payment_method_data);
+            self.deposit_payment_method_data
+                .insert(&deposit_id, &payment_method_data);
+        }
+
+        self.deposits.insert(&deposit_id, &deposit);
+        env::log_str(&format!("Deposit terms updated: {}", deposit_id));
+    }
+
+    /// Replaces the oracle-pegged currencies of a deposit. An empty list
+    /// returns every currency to its fixed `min_conversion_rate`.
+    pub fn set_deposit_price_pegs(&mut self, deposit_id: u64, price_pegs: Vec<DepositPricePeg>) {
+        let caller = env::predecessor_account_id();
+        let deposit = self.deposits.get(&deposit_id).expect("Deposit not found");
+        assert!(self.is_deposit_manager(&caller, &deposit), "Unauthorized");
+
+        if price_pegs.is_empty() {
+            self.deposit_price_pegs.remove(&deposit_id);
+            env::log_str(&format!("Deposit price pegs cleared: {}", deposit_id));
+            return;
+        }
+
+        let payment_method_data = self
+            .deposit_payment_method_data
+            .get(&deposit_id)
+            .unwrap_or_else(|| env::panic_str("Deposit has no currency rates"));
+        let mut normalized: Vec<DepositPricePeg> = Vec::with_capacity(price_pegs.len());
+        for peg in price_pegs {
+            let currency_code = peg.currency_code.trim().to_uppercase();
+            let feed_id = peg.feed_id.trim().to_string();
+            assert!(!feed_id.is_empty(), "feed_id is required");
+            assert!(
+                peg.spread_bps.abs() <= MAX_PRICE_SPREAD_BPS,
+                "spread_bps is out of range"
+            );
+            assert!(
+                payment_method_data.iter().any(|data| data
+                    .currencies
+                    .iter()
+                    .any(|currency| currency.code == currency_code)),
+                "Currency not supported by deposit"
+            );
+            assert!(
+                normalized
+                    .iter()
+                    .all(|existing| existing.currency_code != currency_code),
+                "duplicate currency code"
+            );
+            normalized.push(DepositPricePeg {
+                currency_code,
+                feed_id,
+                spread_bps: peg.spread_bps,
+            });
+        }
+
+        self.deposit_price_pegs.insert(&deposit_id, &normalized);
+        env::log_str(&format!("Deposit price pegs updated: {}", deposit_id));
+    }
+
+    pub fn set_deposit_buyer_policy(&mut self, deposit_id: u64, policy: BuyerPolicy) {
+        let caller = env::predecessor_account_id();
+        let deposit = self.deposits.get(&deposit_id).expect("Deposit not found");
+        assert!(self.is_deposit_manager(&caller, &deposit), "Unauthorized");
+
+        if policy == BuyerPolicy::Open {
+            self.deposit_buyer_policies.remove(&deposit_id);
+        } else {
+            self.deposit_buyer_policies.insert(&deposit_id, &policy);
+        }
+
+        env::log_str(&format!(
+            "Deposit buyer policy updated: {} {:?}",
+            deposit_id, policy
+        ));
+    }
+
+    /// Replaces the deposit's allowlist with `buyers`.
+    pub fn set_deposit_buyer_allowlist(&mut self, deposit_id: u64, buyers: Vec<AccountId>) {
+        let caller = env::predecessor_account_id();
+        let deposit = self.deposits.get(&deposit_id).expect("Deposit not found");
+        assert!(self.is_deposit_manager(&caller, &deposit), "Unauthorized");
+        assert!(
+            buyers.len() <= MAX_BUYER_ALLOWLIST_LEN,
+            "Buyer allowlist is too long"
+        );
+
+        let mut allowlist = self
+            .deposit_buyer_allowlists
+            .get(&deposit_id)
+            .unwrap_or_else(|| UnorderedSet::new(format!("wl:{}", deposit_id).as_bytes()));
+        allowlist.clear();
+        for buyer in &buyers {
+            allowlist.insert(buyer);
+        }
+        self.deposit_buyer_allowlists
+            .insert(&deposit_id, &allowlist);
+
+        env::log_str(&format!(
+            "Deposit buyer allowlist updated: {} ({} buyers)",
+            deposit_id,
+            allowlist.len()
+        ));
+    }
+
+    /// Requires buyers to have at least `min_fulfilled_intents` fulfilled intents
+    /// before signaling on the deposit. Zero removes the requirement.
+    pub fn set_deposit_min_buyer_fulfilled_intents(
+        &mut self,
+        deposit_id: u64,
+        min_fulfilled_intents: u64,
+    ) {
+        let caller = env::predecessor_account_id();
+        let deposit = self.deposits.get(&deposit_id).expect("Deposit not found");
+        assert!(self.is_deposit_manager(&caller, &deposit), "Unauthorized");
+
+        if min_fulfilled_intents == 0 {
+            self.deposit_min_buyer_fulfilled_intents.remove(&deposit_id);
+        } else {
+            self.deposit_min_buyer_fulfilled_intents
+                .insert(&deposit_id, &min_fulfilled_intents);
+        }
+    }
+
     // === V2 SELLER FUNDING METHODS ===
 
     #[payable]
@@ -574,6 +1338,7 @@ impl Contract {
         payment_methods: Vec<String>,
         delegate: Option<AccountId>,
         refund_to: String,
+        payment_method_data: Option<Vec<DepositPaymentMethodData>>,
     ) -> u64 {
         let expected_amount = expected_amount.0;
         let min_intent_amount = min_intent_amount.0;
@@ -595,12 +1360,12 @@ impl Contract {
             !payment_methods.is_empty(),
             "At least one payment method required"
         );
-        assert!(
-            Self::extract_payment_details(&payment_methods).is_some(),
-            "payment_methods must include platform::tagname"
-        );
         assert!(!asset_id.trim().is_empty(), "asset_id is required");
         assert!(!refund_to.trim().is_empty(), "refund_to is required");
+        let payment_method_data =
+            Self::normalize_payment_method_data(&payment_methods, payment_method_data);
+        Self::assert_payee_details(&payment_methods, payment_method_data.as_deref());
+        self.assert_payment_methods_registered(&payment_methods, payment_method_data.as_deref());
 
         self.deposit_counter += 1;
         let deposit_id = self.deposit_counter;
@@ -639,12 +1404,16 @@ impl Contract {
         };
 
         self.deposits.insert(&deposit_id, &deposit);
-        self.deposit_funding.insert(&deposit_id, &funding);
+        self.store_deposit_funding(deposit_id, &funding);
         self.insert_account_deposit(&depositor, deposit_id);
         self.deposit_intents.insert(
             &deposit_id,
             &UnorderedSet::new(format!("di:{}", deposit_id).as_bytes()),
         );
+        if let Some(payment_method_data) = payment_method_data {
+            self.deposit_payment_method_data
+                .insert(&deposit_id, &payment_method_data);
+        }
 
         env::log_str(&format!("V2 deposit intent registered: {}", deposit_id));
         deposit_id
@@ -668,7 +1437,7 @@ impl Contract {
         funding.status = FundingStatus::Cancelled;
         funding.failure_reason = Some("Cancelled by seller".to_string());
         funding.updated_at_ms = self.now_ms();
-        self.deposit_funding.insert(&deposit_id, &funding);
+        self.store_deposit_funding(deposit_id, &funding);
 
         self.remove_open_listing(&funding.asset_id, deposit_id);
         env::log_str(&format!("V2 deposit intent cancelled: {}", deposit_id));
@@ -709,7 +1478,7 @@ impl Contract {
             funding.status = FundingStatus::TopUpExpired;
             funding.failure_reason = Some("Top-up window already expired".to_string());
             funding.updated_at_ms = now_ms;
-            self.deposit_funding.insert(&deposit_id, &funding);
+            self.store_deposit_funding(deposit_id, &funding);
             self.remove_open_listing(&funding.asset_id, deposit_id);
             return;
         }
@@ -729,7 +1498,7 @@ impl Contract {
         funding.last_intents_status = Some("PENDING_DEPOSIT".to_string());
         funding.updated_at_ms = now_ms;
 
-        self.deposit_funding.insert(&deposit_id, &funding);
+        self.store_deposit_funding(deposit_id, &funding);
         env::log_str(&format!("V2 quote set for deposit {}", deposit_id));
     }
 
@@ -765,7 +1534,7 @@ impl Contract {
             self.remove_open_listing(&funding.asset_id, deposit_id);
         }
 
-        self.deposit_funding.insert(&deposit_id, &funding);
+        self.store_deposit_funding(deposit_id, &funding);
     }
 
     pub fn oracle_confirm_funding_v2(
@@ -827,7 +1596,7 @@ impl Contract {
             deposit.max_intent_amount = deposit.min_intent_amount;
         }
 
-        self.deposit_funding.insert(&deposit_id, &funding);
+        self.store_deposit_funding(deposit_id, &funding);
         self.deposits.insert(&deposit_id, &deposit);
 
         self.add_open_listing(&funding.asset_id, deposit_id);
@@ -866,7 +1635,7 @@ impl Contract {
         funding.failure_reason = Some(reason);
         funding.updated_at_ms = self.now_ms();
 
-        self.deposit_funding.insert(&deposit_id, &funding);
+        self.store_deposit_funding(deposit_id, &funding);
         self.remove_open_listing(&funding.asset_id, deposit_id);
     }
 
@@ -900,10 +1669,35 @@ impl Contract {
         funding.failure_reason = Some(reason);
         funding.updated_at_ms = self.now_ms();
 
-        self.deposit_funding.insert(&deposit_id, &funding);
+        self.store_deposit_funding(deposit_id, &funding);
         self.remove_open_listing(&funding.asset_id, deposit_id);
     }
 
+    pub fn oracle_push_price(&mut self, feed_id: String, rate: U128, observed_at_ms: u64) {
+        self.assert_oracle();
+        let feed_id = feed_id.trim().to_string();
+        assert!(!feed_id.is_empty(), "feed_id is required");
+        assert!(rate.0 > 0, "rate must be > 0");
+
+        let now_ms = self.now_ms();
+        assert!(observed_at_ms <= now_ms, "observed_at_ms is in the future");
+        if let Some(current) = self.price_feeds.get(&feed_id) {
+            if observed_at_ms <= current.observed_at_ms {
+                return;
+            }
+        }
+
+        self.price_feeds.insert(
+            &feed_id,
+            &PriceFeed {
+                rate: rate.0,
+                observed_at_ms,
+                updated_at_ms: now_ms,
+            },
+        );
+        env::log_str(&format!("Price pushed for feed {}", feed_id));
+    }
+
     // === ORCHESTRATOR FUNCTIONS (V1 + V2 listing compatibility) ===
 
     #[payable]
@@ -915,18 +1709,166 @@ impl Contract {
         currency_code: String,
         recipient: String,
         chain: String,
+        buyer_encryption_public_key: Option<String>,
     ) -> String {
-        let amount = amount.0;
-        let buyer = env::predecessor_account_id();
-        let mut deposit = self.deposits.get(&deposit_id).expect("Deposit not found");
+        self.create_intent(
+            env::predecessor_account_id(),
+            deposit_id,
+            amount.0,
+            payment_method,
+            currency_code,
+            recipient,
+            chain,
+            buyer_encryption_public_key,
+            Self::attached_collateral(1),
+        )
+        .intent_hash
+    }
 
-        if let Some(funding) = self.deposit_funding.get(&deposit_id) {
-            assert_eq!(
-                funding.status,
-                FundingStatus::Funded,
-                "Listing is not funded"
-            );
-        }
+    /// Signals one intent per leg in a single atomic call, so a buyer can fill
+    /// an order larger than any one deposit's `max_intent_amount`. All legs must
+    /// share the deposit asset and payment platform, and none may require seller
+    /// approval, and the whole batch must fit under `max_intents_per_buyer`.
+    /// The attached deposit beyond each leg's storage deposit is split evenly
+    /// across legs as collateral, with any remainder held on the first leg.
+    #[payable]
+    pub fn signal_intent_batch(
+        &mut self,
+        legs: Vec<IntentLeg>,
+        currency_code: String,
+        recipient: String,
+        chain: String,
+        buyer_encryption_public_key: Option<String>,
+    ) -> String {
+        let buyer = env::predecessor_account_id();
+        assert!(!legs.is_empty(), "At least one leg required");
+        assert!(
+            legs.len() <= MAX_INTENT_GROUP_LEGS,
+            "Too many legs in intent batch"
+        );
+        let open_intents = self.buyer_open_intent_counts.get(&buyer).unwrap_or(0);
+        assert!(
+            self.max_intents_per_buyer == 0
+                || open_intents as usize + legs.len() <= self.max_intents_per_buyer as usize,
+            "Intent batch exceeds max intents per buyer"
+        );
+
+        let first_deposit = self
+            .deposits
+            .get(&legs[0].deposit_id)
+            .expect("Deposit not found");
+        let (platform, _) = Self::parse_payment_method(&legs[0].payment_method);
+        let mut deposit_ids: Vec<u64> = Vec::with_capacity(legs.len());
+        for leg in &legs {
+            assert!(
+                !deposit_ids.contains(&leg.deposit_id),
+                "Duplicate deposit in intent batch"
+            );
+            deposit_ids.push(leg.deposit_id);
+            let deposit = self
+                .deposits
+                .get(&leg.deposit_id)
+                .expect("Deposit not found");
+            assert_eq!(
+                deposit.token, first_deposit.token,
+                "Intent batch legs must share an asset"
+            );
+            assert_eq!(
+                Self::parse_payment_method(&leg.payment_method).0,
+                platform,
+                "Intent batch legs must share a payment platform"
+            );
+        }
+
+        let collateral = Self::attached_collateral(legs.len());
+        let leg_collateral = collateral / legs.len() as u128;
+        let remainder = collateral % legs.len() as u128;
+
+        self.intent_group_counter += 1;
+        let group_id = format!("group:{}", self.intent_group_counter);
+        let mut intent_hashes = Vec::with_capacity(legs.len());
+        for (index, leg) in legs.into_iter().enumerate() {
+            let collateral = if index == 0 {
+                leg_collateral + remainder
+            } else {
+                leg_collateral
+            };
+            let intent = self.create_intent(
+                buyer.clone(),
+                leg.deposit_id,
+                leg.amount.0,
+                leg.payment_method,
+                currency_code.clone(),
+                recipient.clone(),
+                chain.clone(),
+                buyer_encryption_public_key.clone(),
+                collateral,
+            );
+            assert!(
+                intent.status == IntentStatus::Signaled,
+                "Intent batch legs cannot require seller approval"
+            );
+            intent_hashes.push(intent.intent_hash);
+        }
+
+        for intent_hash in &intent_hashes {
+            self.intent_group_ids.insert(intent_hash, &group_id);
+        }
+        let group = IntentGroup {
+            group_id: group_id.clone(),
+            buyer,
+            intent_hashes,
+            created_at_ms: self.now_ms(),
+        };
+        self.intent_groups.insert(&group_id, &group);
+
+        env::log_str(&format!(
+            "Intent group signaled: {} ({} legs)",
+            group_id,
+            group.intent_hashes.len()
+        ));
+        group_id
+    }
+
+    fn create_intent(
+        &mut self,
+        buyer: AccountId,
+        deposit_id: u64,
+        amount: u128,
+        payment_method: String,
+        currency_code: String,
+        recipient: String,
+        chain: String,
+        buyer_encryption_public_key: Option<String>,
+        collateral: u128,
+    ) -> Intent {
+        let mut deposit = self.deposits.get(&deposit_id).expect("Deposit not found");
+
+        if let Some(funding) = self.deposit_funding.get(&deposit_id) {
+            assert_eq!(
+                funding.status,
+                FundingStatus::Funded,
+                "Listing is not funded"
+            );
+        }
+        if Self::is_bitcoin_chain(&chain) {
+            if let Err(err) = bitcoin::parse_address(&recipient, self.bitcoin_network) {
+                env::panic_str(&format!("Invalid bitcoin recipient: {}", err));
+            }
+        }
+        let evm_token = self.evm_tokens.get(&deposit.token);
+        if Self::evm_chain_id(&chain).is_some() || evm_token.is_some() {
+            assert!(
+                ethereum::parse_address(&recipient).is_some(),
+                "Invalid EVM recipient: expected a 0x-prefixed 20-byte address"
+            );
+        }
+        if let Some(token) = evm_token {
+            assert!(
+                Self::evm_chain_id(&chain) == Some(token.chain_id),
+                "Intent chain does not match the EVM token's chain"
+            );
+        }
 
         assert!(amount >= deposit.min_intent_amount, "Amount below minimum");
         assert!(amount <= deposit.max_intent_amount, "Amount above maximum");
@@ -935,20 +1877,67 @@ impl Contract {
             "Insufficient liquidity"
         );
         assert!(
-            deposit.payment_methods.contains(&payment_method),
-            "Payment method not supported"
+            collateral >= self.min_intent_collateral_yocto,
+            "Attached deposit is below intent collateral"
         );
-
-        let deposit_intent_vec = self.deposit_intents.get(&deposit_id).unwrap();
+        let cooldown_key = Self::buyer_deposit_cooldown_key(&buyer, deposit_id);
+        if let Some(cooldown_until_ms) = self.buyer_deposit_cooldowns.get(&cooldown_key) {
+            assert!(
+                self.now_ms() >= cooldown_until_ms,
+                "Deposit is in cancel cooldown for buyer"
+            );
+            self.buyer_deposit_cooldowns.remove(&cooldown_key);
+        }
+        if let Some(min_fulfilled) = self.deposit_min_buyer_fulfilled_intents.get(&deposit_id) {
+            let fulfilled = self
+                .account_stats
+                .get(&buyer)
+                .map(|stats| stats.buyer.fulfilled)
+                .unwrap_or(0);
+            assert!(
+                fulfilled >= min_fulfilled,
+                "Buyer does not meet deposit reputation requirement"
+            );
+        }
+        let payment_method_index = deposit
+            .payment_methods
+            .iter()
+            .position(|candidate| candidate == &payment_method)
+            .unwrap_or_else(|| env::panic_str("Payment method not supported"));
+        let (currency_code, conversion_rate) =
+            self.resolve_intent_currency(deposit_id, payment_method_index, &currency_code);
+        let (platform, _) = Self::parse_payment_method(&payment_method);
         assert!(
-            deposit_intent_vec.len() < self.max_intents_per_deposit as u64,
-            "Max intents reached"
+            self.registered_payment_method(&platform)
+                .currencies
+                .contains(&currency_code),
+            "Currency not supported by payment method"
         );
 
-        deposit.remaining_deposits -= amount;
-        deposit.outstanding_intents += amount;
-        self.deposits.insert(&deposit_id, &deposit);
-        self.sync_open_listing_state(deposit_id, &deposit);
+        let requires_approval = match self
+            .deposit_buyer_policies
+            .get(&deposit_id)
+            .unwrap_or(BuyerPolicy::Open)
+        {
+            BuyerPolicy::Open => false,
+            BuyerPolicy::Allowlist => {
+                assert!(
+                    self.is_allowlisted_buyer(deposit_id, &buyer),
+                    "Buyer is not allowlisted for deposit"
+                );
+                false
+            }
+            BuyerPolicy::ManualApproval => !self.is_allowlisted_buyer(deposit_id, &buyer),
+        };
+
+        self.open_buyer_intent_slot(&buyer);
+        if !requires_approval {
+            self.assert_deposit_intent_slot(deposit_id);
+            deposit.remaining_deposits -= amount;
+            deposit.outstanding_intents += amount;
+            self.deposits.insert(&deposit_id, &deposit);
+            self.sync_open_listing_state(deposit_id, &deposit);
+        }
 
         self.intent_counter += 1;
         let intent_hash = format!("intent:{}", self.intent_counter);
@@ -961,12 +1950,36 @@ impl Contract {
             timestamp: env::block_timestamp(),
             payment_method,
             currency_code,
-            status: IntentStatus::Signaled,
+            status: if requires_approval {
+                IntentStatus::PendingApproval
+            } else {
+                IntentStatus::Signaled
+            },
             recipient,
             chain,
         };
 
         self.intents.insert(&intent_hash, &intent);
+        self.intent_conversion_rates
+            .insert(&intent_hash, &conversion_rate);
+        if collateral > 0 {
+            self.intent_collateral.insert(&intent_hash, &collateral);
+        }
+        self.update_intent_stats(&intent, &deposit.depositor, |stats| stats.signaled += 1);
+        if self.intent_payee_details_hash(&intent).is_some() {
+            let buyer_encryption_public_key = buyer_encryption_public_key
+                .map(|key| key.trim().to_lowercase())
+                .filter(|key| decode_hex_fixed::<32>(key).is_some())
+                .expect("buyer_encryption_public_key must be 32-byte hex");
+            self.intent_payee_reveals.insert(
+                &intent_hash,
+                &PayeeDetailsReveal {
+                    buyer_encryption_public_key,
+                    encrypted_payee_details: None,
+                    revealed_at_ms: 0,
+                },
+            );
+        }
 
         let mut buyer_intents = self
             .account_intents
@@ -980,51 +1993,461 @@ impl Contract {
         self.deposit_intents
             .insert(&deposit_id, &deposit_intent_set);
 
-        env::log_str(&format!("Intent signaled: {}", intent_hash));
-        intent_hash
+        if requires_approval {
+            env::log_str(&format!("Intent pending approval: {}", intent_hash));
+        } else {
+            self.insert_deposit_active_intent(deposit_id, &intent_hash);
+            env::log_str(&format!("Intent signaled: {}", intent_hash));
+        }
+        intent
     }
 
     pub fn cancel_intent(&mut self, intent_hash: String) {
+        self.cancel_buyer_intent(env::predecessor_account_id(), intent_hash);
+    }
+
+    /// Cancels every leg of the group that is still signaled or pending approval.
+    pub fn cancel_intent_group(&mut self, group_id: String) {
         let caller = env::predecessor_account_id();
+        let group = self
+            .intent_groups
+            .get(&group_id)
+            .expect("Intent group not found");
+        assert!(group.buyer == caller, "Only buyer can cancel");
+
+        let mut cancelled = 0;
+        for intent_hash in group.intent_hashes {
+            let cancellable = self.intents.get(&intent_hash).is_some_and(|intent| {
+                intent.status == IntentStatus::Signaled
+                    || intent.status == IntentStatus::PendingApproval
+            });
+            if cancellable {
+                self.cancel_buyer_intent(caller.clone(), intent_hash);
+                cancelled += 1;
+            }
+        }
+        assert!(cancelled > 0, "No cancellable intents in group");
+
+        env::log_str(&format!(
+            "Intent group cancelled: {} ({} legs)",
+            group_id, cancelled
+        ));
+    }
+
+    fn cancel_buyer_intent(&mut self, caller: AccountId, intent_hash: String) {
         let mut intent = self.intents.get(&intent_hash).expect("Intent not found");
 
         assert!(intent.buyer == caller, "Only buyer can cancel");
         assert!(
-            intent.status == IntentStatus::Signaled,
+            intent.status == IntentStatus::Signaled
+                || intent.status == IntentStatus::PendingApproval,
             "Intent not in signaled state"
         );
 
+        let was_signaled = intent.status == IntentStatus::Signaled;
+        if was_signaled {
+            let mut deposit = self.deposits.get(&intent.deposit_id).unwrap();
+            deposit.remaining_deposits += intent.amount;
+            deposit.outstanding_intents -= intent.amount;
+            self.deposits.insert(&intent.deposit_id, &deposit);
+            self.sync_open_listing_state(intent.deposit_id, &deposit);
+        }
+
+        intent.status = IntentStatus::Cancelled;
+        self.intents.insert(&intent_hash, &intent);
+        let depositor = self.deposits.get(&intent.deposit_id).unwrap().depositor;
+        self.update_intent_stats(&intent, &depositor, |stats| stats.cancelled += 1);
+        self.start_buyer_deposit_cooldown(&caller, intent.deposit_id);
+        self.close_active_intent(&intent);
+        self.release_intent_collateral(&intent_hash, &caller);
+
+        env::log_str(&format!("Intent cancelled: {}", intent_hash));
+    }
+
+    /// Accepts a `PendingApproval` intent, locking its amount and taking one of
+    /// the deposit's `max_intents_per_deposit` slots. The expiration period
+    /// still runs from when the intent was signaled. Callable by the depositor
+    /// or delegate.
+    pub fn approve_intent(&mut self, intent_hash: String) {
+        let caller = env::predecessor_account_id();
+        let mut intent = self.intents.get(&intent_hash).expect("Intent not found");
         let mut deposit = self.deposits.get(&intent.deposit_id).unwrap();
-        deposit.remaining_deposits += intent.amount;
-        deposit.outstanding_intents -= intent.amount;
+        assert!(self.is_deposit_manager(&caller, &deposit), "Unauthorized");
+        assert!(
+            intent.status == IntentStatus::PendingApproval,
+            "Intent not pending approval"
+        );
+        assert!(!self.is_intent_expired(&intent), "Intent has expired");
+        if let Some(funding) = self.deposit_funding.get(&intent.deposit_id) {
+            assert_eq!(
+                funding.status,
+                FundingStatus::Funded,
+                "Listing is not funded"
+            );
+        }
+        assert!(
+            deposit.remaining_deposits >= intent.amount,
+            "Insufficient liquidity"
+        );
+
+        self.assert_deposit_intent_slot(intent.deposit_id);
+        self.insert_deposit_active_intent(intent.deposit_id, &intent_hash);
+        deposit.remaining_deposits -= intent.amount;
+        deposit.outstanding_intents += intent.amount;
         self.deposits.insert(&intent.deposit_id, &deposit);
         self.sync_open_listing_state(intent.deposit_id, &deposit);
 
-        intent.status = IntentStatus::Cancelled;
+        intent.status = IntentStatus::Signaled;
         self.intents.insert(&intent_hash, &intent);
 
-        env::log_str(&format!("Intent cancelled: {}", intent_hash));
+        env::log_str(&format!("Intent approved: {}", intent_hash));
+    }
+
+    pub fn reject_intent(&mut self, intent_hash: String) {
+        let caller = env::predecessor_account_id();
+        let mut intent = self.intents.get(&intent_hash).expect("Intent not found");
+        let deposit = self.deposits.get(&intent.deposit_id).unwrap();
+        assert!(self.is_deposit_manager(&caller, &deposit), "Unauthorized");
+        assert!(
+            intent.status == IntentStatus::PendingApproval,
+            "Intent not pending approval"
+        );
+
+        intent.status = IntentStatus::Rejected;
+        self.intents.insert(&intent_hash, &intent);
+        self.start_buyer_deposit_cooldown(&intent.buyer, intent.deposit_id);
+        self.close_active_intent(&intent);
+        self.release_intent_collateral(&intent_hash, &intent.buyer);
+
+        env::log_str(&format!("Intent rejected: {}", intent_hash));
     }
 
-    pub fn fulfill_intent(&mut self, intent_hash: String) -> Promise {
+    /// Returns an unpaid intent's amount to the deposit once
+    /// `intent_expiration_period` has passed, paying the buyer's collateral to
+    /// the depositor. Callable by anyone.
+    pub fn expire_intent(&mut self, intent_hash: String) {
         let mut intent = self.intents.get(&intent_hash).expect("Intent not found");
+        assert!(
+            intent.status == IntentStatus::Signaled
+                || intent.status == IntentStatus::PendingApproval,
+            "Intent not in signaled state"
+        );
+        assert!(self.is_intent_expired(&intent), "Intent has not expired");
+
+        // An intent the seller never approved locked nothing, so the buyer keeps the collateral.
+        let mut deposit = self.deposits.get(&intent.deposit_id).unwrap();
+        let was_signaled = intent.status == IntentStatus::Signaled;
+        let collateral_recipient = if was_signaled {
+            deposit.remaining_deposits += intent.amount;
+            deposit.outstanding_intents -= intent.amount;
+            self.deposits.insert(&intent.deposit_id, &deposit);
+            self.sync_open_listing_state(intent.deposit_id, &deposit);
+            deposit.depositor.clone()
+        } else {
+            intent.buyer.clone()
+        };
+
+        intent.status = IntentStatus::Expired;
+        self.intents.insert(&intent_hash, &intent);
+        self.update_intent_stats(&intent, &deposit.depositor, |stats| stats.expired += 1);
+        self.close_active_intent(&intent);
+        self.release_intent_collateral(&intent_hash, &collateral_recipient);
+
+        env::log_str(&format!("Intent expired: {}", intent_hash));
+    }
+
+    /// Checks up to `limit` intents of the deposit's history, starting at
+    /// `from_index`, and deletes those that have been terminal for
+    /// `intent_retention_ms`, dropping them from their intent group. Attestation
+    /// sessions stay marked as used. Callable by anyone.
+    pub fn gc_deposit_intents(
+        &mut self,
+        deposit_id: u64,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> IntentCollection {
+        let mut index = from_index.unwrap_or(0);
+        let limit = self.normalized_limit(limit);
+        let mut history = self
+            .deposit_intents
+            .get(&deposit_id)
+            .expect("Deposit not found");
+
+        let mut removed = 0;
+        for _ in 0..limit {
+            let Some(intent_hash) = history.as_vector().get(index) else {
+                break;
+            };
+            let intent = self.intents.get(&intent_hash);
+            if intent
+                .as_ref()
+                .is_some_and(|intent| !self.is_intent_collectable(intent))
+            {
+                index += 1;
+                continue;
+            }
+
+            // Removal moves the last intent into this slot, so `index` stays put.
+            history.remove(&intent_hash);
+            let Some(intent) = intent else {
+                continue;
+            };
+            if let Some(mut buyer_intents) = self.account_intents.get(&intent.buyer) {
+                buyer_intents.remove(&intent_hash);
+                self.account_intents.insert(&intent.buyer, &buyer_intents);
+            }
+            self.intents.remove(&intent_hash);
+            self.intent_conversion_rates.remove(&intent_hash);
+            self.intent_payee_reveals.remove(&intent_hash);
+            self.intent_attestations.remove(&intent_hash);
+            self.intent_disputes.remove(&intent_hash);
+            self.intent_closed_at_ms.remove(&intent_hash);
+            self.remove_intent_from_group(&intent_hash);
+            removed += 1;
+        }
+        self.deposit_intents.insert(&deposit_id, &history);
+
+        env::log_str(&format!(
+            "Deposit intents collected: {} ({} removed)",
+            deposit_id, removed
+        ));
+        IntentCollection {
+            removed,
+            next_index: (index < history.len()).then_some(index),
+        }
+    }
+
+    /// Posts the payee handle for a hashed payment method, encrypted to the
+    /// buyer's `buyer_encryption_public_key`. Callable by the depositor or delegate.
+    pub fn reveal_payee_details(&mut self, intent_hash: String, encrypted_payee_details: String) {
+        let caller = env::predecessor_account_id();
+        let intent = self.intents.get(&intent_hash).expect("Intent not found");
+        let deposit = self.deposits.get(&intent.deposit_id).unwrap();
+        assert!(self.is_deposit_manager(&caller, &deposit), "Unauthorized");
         assert!(
             intent.status == IntentStatus::Signaled,
             "Intent not in signaled state"
         );
 
-        intent.status = IntentStatus::Fulfilled;
+        let encrypted_payee_details = encrypted_payee_details.trim().to_string();
+        assert!(
+            !encrypted_payee_details.is_empty(),
+            "encrypted_payee_details is required"
+        );
+        assert!(
+            encrypted_payee_details.len() <= 2_048,
+            "encrypted_payee_details is too large"
+        );
+
+        let mut reveal = self
+            .intent_payee_reveals
+            .get(&intent_hash)
+            .expect("Intent does not use hashed payee details");
+        reveal.encrypted_payee_details = Some(encrypted_payee_details);
+        reveal.revealed_at_ms = self.now_ms();
+        self.intent_payee_reveals.insert(&intent_hash, &reveal);
+
+        env::log_str(&format!("Payee details revealed: {}", intent_hash));
+    }
+
+    /// Marks the intent fulfilled. With a non-zero `dispute_window_ms` the
+    /// payout and the buyer's collateral are held until `claim_intent_payout`
+    /// after the window. Without an
+    /// attestation the fulfillment is not counted in account stats unless a
+    /// dispute over it is resolved as fulfilled.
+    pub fn fulfill_intent(&mut self, intent_hash: String) -> PromiseOrValue<()> {
+        self.complete_fulfillment(intent_hash, false)
+    }
+
+    pub fn claim_intent_payout(&mut self, intent_hash: String) -> Promise {
+        let intent = self.intents.get(&intent_hash).expect("Intent not found");
+        assert!(
+            intent.status == IntentStatus::Fulfilled,
+            "Intent not in fulfilled state"
+        );
+        let release_at_ms = self
+            .intent_payout_release_at_ms
+            .get(&intent_hash)
+            .unwrap_or_else(|| env::panic_str("No held payout for intent"));
+        assert!(
+            self.now_ms() >= release_at_ms,
+            "Dispute window has not elapsed"
+        );
+        self.intent_payout_release_at_ms.remove(&intent_hash);
+        self.intent_fulfilled_at_ms.remove(&intent_hash);
+        self.intent_closed_at_ms
+            .insert(&intent_hash, &self.now_ms());
+        self.release_intent_collateral(&intent_hash, &intent.buyer);
+
+        env::log_str(&format!("Intent payout claimed: {}", intent_hash));
+        self.sign_transaction(intent)
+    }
+
+    // === DISPUTES ===
+
+    /// Opens a dispute on a signaled intent, or on a fulfilled intent whose
+    /// payout is still held. Callable by the buyer, depositor or delegate.
+    pub fn open_dispute(&mut self, intent_hash: String, evidence_hashes: Vec<String>) {
+        let caller = env::predecessor_account_id();
+        let mut intent = self.intents.get(&intent_hash).expect("Intent not found");
+        let deposit = self.deposits.get(&intent.deposit_id).unwrap();
+        assert!(
+            intent.buyer == caller || self.is_deposit_manager(&caller, &deposit),
+            "Unauthorized"
+        );
+        match intent.status {
+            IntentStatus::Signaled => {}
+            IntentStatus::Fulfilled => {
+                let release_at_ms = self
+                    .intent_payout_release_at_ms
+                    .get(&intent_hash)
+                    .unwrap_or_else(|| env::panic_str("Intent payout already released"));
+                assert!(self.now_ms() < release_at_ms, "Dispute window has elapsed");
+            }
+            _ => env::panic_str("Intent cannot be disputed in its current state"),
+        }
+
+        let dispute = IntentDispute {
+            opened_by: caller,
+            opened_at_ms: self.now_ms(),
+            status_before: intent.status.clone(),
+            evidence_hashes: Self::normalize_evidence_hashes(evidence_hashes, Vec::new()),
+            outcome: None,
+            resolved_by: None,
+            resolved_at_ms: 0,
+            reason: String::new(),
+        };
+        self.intent_disputes.insert(&intent_hash, &dispute);
+
+        intent.status = IntentStatus::Disputed;
         self.intents.insert(&intent_hash, &intent);
+        self.update_intent_stats(&intent, &deposit.depositor, |stats| stats.disputed += 1);
+
+        env::log_str(&format!("Intent disputed: {}", intent_hash));
+    }
+
+    pub fn submit_dispute_evidence(&mut self, intent_hash: String, evidence_hashes: Vec<String>) {
+        let caller = env::predecessor_account_id();
+        let intent = self.intents.get(&intent_hash).expect("Intent not found");
+        let deposit = self.deposits.get(&intent.deposit_id).unwrap();
+        assert!(
+            intent.buyer == caller || self.is_deposit_manager(&caller, &deposit),
+            "Unauthorized"
+        );
+        assert!(
+            intent.status == IntentStatus::Disputed,
+            "Intent not in disputed state"
+        );
+
+        let mut dispute = self.intent_disputes.get(&intent_hash).unwrap();
+        dispute.evidence_hashes =
+            Self::normalize_evidence_hashes(evidence_hashes, dispute.evidence_hashes);
+        self.intent_disputes.insert(&intent_hash, &dispute);
+
+        env::log_str(&format!("Dispute evidence submitted: {}", intent_hash));
+    }
+
+    /// Settles a dispute. `Fulfill` pays the buyer immediately; `Cancel` returns
+    /// the amount to the deposit and pays the buyer's collateral to the depositor.
+    pub fn resolve_dispute(
+        &mut self,
+        intent_hash: String,
+        outcome: DisputeOutcome,
+        reason: String,
+    ) -> PromiseOrValue<()> {
+        let caller = env::predecessor_account_id();
+        assert!(
+            caller == self.arbiter(),
+            "Only arbiter can resolve disputes"
+        );
+        let mut intent = self.intents.get(&intent_hash).expect("Intent not found");
+        assert!(
+            intent.status == IntentStatus::Disputed,
+            "Intent not in disputed state"
+        );
+        let reason = reason.trim().to_string();
+        assert!(!reason.is_empty(), "reason is required");
+        assert!(reason.len() <= MAX_DISPUTE_REASON_LEN, "reason is too long");
+
+        let mut dispute = self.intent_disputes.get(&intent_hash).unwrap();
+        let was_signaled = dispute.status_before == IntentStatus::Signaled;
+        self.intent_payout_release_at_ms.remove(&intent_hash);
+        let fulfilled_at_ms = self.intent_fulfilled_at_ms.remove(&intent_hash);
+        // Held payouts only counted as fulfilled when they came with an attestation.
+        let counted_fulfillment =
+            !was_signaled && self.intent_attestations.get(&intent_hash).is_some();
 
         let mut deposit = self.deposits.get(&intent.deposit_id).unwrap();
-        deposit.outstanding_intents -= intent.amount;
+        if was_signaled {
+            deposit.outstanding_intents -= intent.amount;
+        }
+        if outcome == DisputeOutcome::Cancel {
+            deposit.remaining_deposits += intent.amount;
+        }
         self.deposits.insert(&intent.deposit_id, &deposit);
+        self.sync_open_listing_state(intent.deposit_id, &deposit);
 
-        env::log_str(&format!("Intent fulfilled: {}", intent_hash));
-        self.sign_transaction(intent)
+        intent.status = match outcome {
+            DisputeOutcome::Fulfill => IntentStatus::Fulfilled,
+            DisputeOutcome::Cancel => IntentStatus::Cancelled,
+        };
+        self.intents.insert(&intent_hash, &intent);
+        if was_signaled {
+            self.close_active_intent(&intent);
+        } else {
+            self.intent_closed_at_ms
+                .insert(&intent_hash, &self.now_ms());
+        }
+        let collateral_recipient = match outcome {
+            DisputeOutcome::Fulfill => intent.buyer.clone(),
+            DisputeOutcome::Cancel => deposit.depositor.clone(),
+        };
+        self.release_intent_collateral(&intent_hash, &collateral_recipient);
+        let held_time_to_fulfill_ms = fulfilled_at_ms
+            .map(|at_ms| at_ms.saturating_sub(intent.timestamp / 1_000_000))
+            .unwrap_or(0);
+        match outcome {
+            DisputeOutcome::Fulfill if counted_fulfillment => {}
+            DisputeOutcome::Fulfill => {
+                let time_to_fulfill_ms = if was_signaled {
+                    self.intent_age_ms(&intent)
+                } else {
+                    held_time_to_fulfill_ms
+                };
+                self.update_intent_stats(&intent, &deposit.depositor, |stats| {
+                    stats.record_fulfillment(&deposit.token, intent.amount, time_to_fulfill_ms)
+                });
+            }
+            DisputeOutcome::Cancel => {
+                // A cancelled dispute over a held payout undoes the fulfillment it counted.
+                let time_to_fulfill_ms = held_time_to_fulfill_ms;
+                self.update_intent_stats(&intent, &deposit.depositor, |stats| {
+                    if counted_fulfillment {
+                        stats.revert_fulfillment(&deposit.token, intent.amount, time_to_fulfill_ms);
+                    }
+                    stats.cancelled += 1;
+                });
+            }
+        }
+
+        dispute.outcome = Some(outcome);
+        dispute.resolved_by = Some(caller);
+        dispute.resolved_at_ms = self.now_ms();
+        dispute.reason = reason;
+        self.intent_disputes.insert(&intent_hash, &dispute);
+
+        env::log_str(&format!("Dispute resolved: {} {:?}", intent_hash, outcome));
+        match outcome {
+            DisputeOutcome::Fulfill => PromiseOrValue::Promise(self.sign_transaction(intent)),
+            DisputeOutcome::Cancel => PromiseOrValue::Value(()),
+        }
     }
 
-    pub fn fulfill_intent_with_proof(&mut self, intent_hash: String, proof: String) -> Promise {
+    pub fn fulfill_intent_with_proof(
+        &mut self,
+        intent_hash: String,
+        proof: String,
+    ) -> PromiseOrValue<()> {
         let normalized_proof = proof.trim();
         assert!(!normalized_proof.is_empty(), "proof is required");
         assert!(
@@ -1045,7 +2468,8 @@ impl Contract {
         &mut self,
         intent_hash: String,
         attestation: String,
-    ) -> Promise {
+        payee_details_salt: Option<String>,
+    ) -> PromiseOrValue<()> {
         let normalized_attestation = attestation.trim();
         assert!(
             !normalized_attestation.is_empty(),
@@ -1060,7 +2484,7 @@ impl Contract {
             "attestation public key is not configured"
         );
 
-        let payload: AttestationPayload = near_sdk::serde_json::from_str(normalized_attestation)
+        let payload: AttestationRecord = near_sdk::serde_json::from_str(normalized_attestation)
             .unwrap_or_else(|_| env::panic_str("invalid attestation payload JSON"));
 
         let intent = self.intents.get(&intent_hash).expect("Intent not found");
@@ -1075,9 +2499,9 @@ impl Contract {
             self.used_attestation_sessions.get(&session_id).is_none(),
             "attestation session already used"
         );
-        assert_eq!(
-            payload.version.trim(),
-            ATTESTATION_VERSION,
+        let version = payload.version.trim();
+        assert!(
+            version == ATTESTATION_VERSION_V1 || version == ATTESTATION_VERSION_V2,
             "unsupported attestation version"
         );
         assert!(
@@ -1088,6 +2512,28 @@ impl Contract {
             payload.expires_at_ms >= self.now_ms(),
             "attestation has expired"
         );
+        assert!(
+            payload.issued_at_ms >= intent.timestamp / 1_000_000,
+            "attestation was issued before the intent was signaled"
+        );
+        assert!(
+            payload.issued_at_ms <= payload.expires_at_ms,
+            "attestation issued_at_ms is after expires_at_ms"
+        );
+        if let Some(payment_at_ms) = payload.payment_at_ms {
+            assert_eq!(
+                version, ATTESTATION_VERSION_V2,
+                "attestation payment_at_ms requires v2"
+            );
+            assert!(
+                payment_at_ms >= intent.timestamp / 1_000_000,
+                "attested payment was made before the intent was signaled"
+            );
+            assert!(
+                payment_at_ms <= payload.issued_at_ms,
+                "attested payment was made after the attestation was issued"
+            );
+        }
 
         let payload_intent_id = payload
             .intent_id
@@ -1121,6 +2567,7 @@ impl Contract {
             payload.checks.amount_match.unwrap_or(false),
             "attestation amount check failed"
         );
+        self.assert_attested_amount_covers_intent(&intent, expected_amount);
 
         assert_eq!(
             payload
@@ -1153,11 +2600,26 @@ impl Contract {
             payload.checks.platform_match.unwrap_or(false),
             "attestation platform check failed"
         );
-        assert_eq!(
-            payload.expected_tagname.as_deref().unwrap_or("").trim(),
-            expected_tagname,
-            "attestation tagname mismatch"
-        );
+        self.assert_attested_server_name_allowed(&expected_platform, &payload.server_name);
+        let attested_tagname = payload.expected_tagname.as_deref().unwrap_or("").trim();
+        match self.intent_payee_details_hash(&intent) {
+            Some(payee_details_hash) => {
+                assert!(
+                    !attested_tagname.is_empty(),
+                    "attestation expected_tagname is required"
+                );
+                let salt = payee_details_salt.expect("payee_details_salt is required");
+                assert_eq!(
+                    Self::compute_payee_details_hash(&expected_platform, attested_tagname, &salt),
+                    payee_details_hash,
+                    "attestation payee details hash mismatch"
+                );
+            }
+            None => assert_eq!(
+                attested_tagname, expected_tagname,
+                "attestation tagname mismatch"
+            ),
+        }
         assert!(
             payload.checks.tagname_match.unwrap_or(false),
             "attestation tagname check failed"
@@ -1171,26 +2633,12 @@ impl Contract {
             "attestation transcript body was not revealed"
         );
 
-        assert_eq!(
-            payload.signature.algorithm.trim().to_lowercase(),
-            "ed25519",
-            "unsupported attestation signature algorithm"
-        );
-        assert_eq!(
-            payload.signature.public_key_hex.trim().to_lowercase(),
-            self.attestation_public_key_hex.trim().to_lowercase(),
-            "attestation signer is not trusted"
-        );
-
-        let signature = Self::decode_hex_fixed::<64>(&payload.signature.signature_hex)
-            .unwrap_or_else(|| env::panic_str("invalid attestation signature hex"));
-        let public_key = Self::decode_hex_fixed::<32>(&payload.signature.public_key_hex)
-            .unwrap_or_else(|| env::panic_str("invalid attestation public key hex"));
-        let canonical_message = Self::build_attestation_canonical_message(&payload);
-        assert!(
-            env::ed25519_verify(&signature, canonical_message.as_bytes(), &public_key),
-            "attestation signature verification failed"
-        );
+        verify_signature(
+            &payload,
+            &self.attestation_public_key_hex,
+            env::ed25519_verify,
+        )
+        .unwrap_or_else(|error| panic!("{}", error));
 
         self.used_attestation_sessions.insert(&session_id, &true);
         self.intent_attestations
@@ -1201,7 +2649,7 @@ impl Contract {
             intent_hash, session_id
         ));
 
-        self.fulfill_intent(intent_hash)
+        self.complete_fulfillment(intent_hash, true)
     }
 
     pub fn release_intent(&mut self, intent_hash: String) -> Promise {
@@ -1221,6 +2669,12 @@ impl Contract {
         let mut deposit = self.deposits.get(&intent.deposit_id).unwrap();
         deposit.outstanding_intents -= intent.amount;
         self.deposits.insert(&intent.deposit_id, &deposit);
+        self.close_active_intent(&intent);
+        self.release_intent_collateral(&intent_hash, &intent.buyer);
+        let time_to_fulfill_ms = self.intent_age_ms(&intent);
+        self.update_intent_stats(&intent, &deposit.depositor, |stats| {
+            stats.record_fulfillment(&deposit.token, intent.amount, time_to_fulfill_ms)
+        });
 
         env::log_str(&format!("Intent released: {}", intent_hash));
         self.sign_transaction(intent)
@@ -1228,27 +2682,66 @@ impl Contract {
 
     // === PAYMENT METHOD REGISTRY ===
 
-    pub fn add_payment_method(&mut self, name: String, verifier: String, currencies: Vec<String>) {
+    pub fn add_payment_method(
+        &mut self,
+        name: String,
+        verifier: String,
+        currencies: Vec<String>,
+        server_names: Option<Vec<String>>,
+    ) {
         self.assert_owner();
+        let name = Self::payment_method_key(&name);
+        assert!(!name.is_empty(), "Payment method name is required");
+        assert!(
+            !name.contains("::"),
+            "Payment method name must not contain ::"
+        );
         assert!(!currencies.is_empty(), "At least one currency required");
+        let mut normalized_currencies: Vec<String> = Vec::with_capacity(currencies.len());
+        for code in currencies {
+            let code = code.trim().to_uppercase();
+            assert!(!code.is_empty(), "currency code is required");
+            if !normalized_currencies.contains(&code) {
+                normalized_currencies.push(code);
+            }
+        }
 
         let pm = PaymentMethod {
             name: name.clone(),
             verifier,
-            currencies,
+            currencies: normalized_currencies,
             initialized: true,
         };
 
         self.payment_methods.insert(&name, &pm);
+        self.payment_method_names.insert(&name);
+        if let Some(server_names) = server_names {
+            self.store_payment_method_server_names(&name, server_names);
+        }
         env::log_str(&format!("Payment method added: {}", name));
     }
 
     pub fn remove_payment_method(&mut self, name: String) {
         self.assert_owner();
+        let name = Self::payment_method_key(&name);
         self.payment_methods.remove(&name);
+        self.payment_method_names.remove(&name);
+        self.payment_method_server_names.remove(&name);
         env::log_str(&format!("Payment method removed: {}", name));
     }
 
+    pub fn set_payment_method_server_names(&mut self, name: String, server_names: Vec<String>) {
+        self.assert_owner();
+        assert!(
+            self.payment_methods
+                .get(&Self::payment_method_key(&name))
+                .is_some(),
+            "Payment method not found"
+        );
+        self.store_payment_method_server_names(&name, server_names);
+        env::log_str(&format!("Payment method server names updated: {}", name));
+    }
+
     // === VIEW FUNCTIONS ===
 
     pub fn get_owner(&self) -> AccountId {
@@ -1256,7 +2749,29 @@ impl Contract {
     }
 
     pub fn get_payment_method(&self, name: String) -> Option<PaymentMethod> {
-        self.payment_methods.get(&name)
+        self.payment_methods.get(&Self::payment_method_key(&name))
+    }
+
+    pub fn get_payment_methods(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<PaymentMethod> {
+        let start = from_index.unwrap_or(0);
+        let limit = self.normalized_limit(limit) as u64;
+        let names = self.payment_method_names.as_vector();
+        let end = names.len().min(start.saturating_add(limit));
+
+        (start..end)
+            .filter_map(|index| names.get(index))
+            .filter_map(|name| self.payment_methods.get(&name))
+            .collect()
+    }
+
+    pub fn get_payment_method_server_names(&self, name: String) -> Vec<String> {
+        self.payment_method_server_names
+            .get(&Self::payment_method_key(&name))
+            .unwrap_or_default()
     }
 
     pub fn get_deposit(&self, deposit_id: u64) -> Option<Deposit> {
@@ -1267,7 +2782,7 @@ impl Contract {
         self.account_deposits
             .get(&account_id)
             .map(|set| set.to_vec())
-            .unwrap_or_else(|| vec![])
+            .unwrap_or_default()
     }
 
     pub fn get_intent(&self, intent_hash: String) -> Option<Intent> {
@@ -1280,6 +2795,12 @@ impl Contract {
     ) -> Option<IntentTransferDetailsView> {
         let intent = self.intents.get(&intent_hash)?;
         let (platform, tagname) = Self::parse_payment_method(&intent.payment_method);
+        let conversion_rate = self.intent_conversion_rates.get(&intent_hash);
+        let fiat_amount_due = conversion_rate
+            .and_then(|rate| Self::fiat_amount_due(intent.amount, rate))
+            .map(|minor| {
+                Self::format_fiat_amount(minor, Self::currency_decimals(&intent.currency_code))
+            });
         Some(IntentTransferDetailsView {
             intent_hash: intent.intent_hash.clone(),
             deposit_id: intent.deposit_id,
@@ -1289,21 +2810,90 @@ impl Contract {
             platform,
             tagname,
             memo: Self::build_intent_transfer_memo(&intent.intent_hash, intent.deposit_id),
+            conversion_rate,
+            fiat_amount_due,
+            payee_details_hash: self.intent_payee_details_hash(&intent),
+            encrypted_payee_details: self
+                .intent_payee_reveals
+                .get(&intent_hash)
+                .and_then(|reveal| reveal.encrypted_payee_details),
         })
     }
 
-    pub fn get_account_intents(&self, account_id: AccountId) -> Vec<String> {
+    /// Intents of the account, at most `MAX_VIEW_LIMIT` when `limit` is not given.
+    pub fn get_account_intents(
+        &self,
+        account_id: AccountId,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<String> {
+        let start = from_index.unwrap_or(0) as usize;
+        let limit = limit.map_or(MAX_VIEW_LIMIT, |limit| self.normalized_limit(Some(limit)));
         self.account_intents
             .get(&account_id)
-            .map(|set| set.to_vec())
-            .unwrap_or_else(|| vec![])
+            .map(|set| set.iter().skip(start).take(limit).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn get_account_intent_records(
+        &self,
+        account_id: AccountId,
+        filter: Option<IntentFilter>,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> IntentPage {
+        self.page_intents(
+            self.account_intents.get(&account_id),
+            filter.unwrap_or_default(),
+            from_index,
+            limit,
+        )
+    }
+
+    pub fn get_deposit_intent_records(
+        &self,
+        deposit_id: u64,
+        filter: Option<IntentFilter>,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> IntentPage {
+        self.page_intents(
+            self.deposit_intents.get(&deposit_id),
+            filter.unwrap_or_default(),
+            from_index,
+            limit,
+        )
     }
 
-    pub fn get_deposit_intents(&self, deposit_id: u64) -> Vec<String> {
+    /// Intent history for the deposit, including terminal intents not yet
+    /// garbage-collected. At most `MAX_VIEW_LIMIT` when `limit` is not given.
+    pub fn get_deposit_intents(
+        &self,
+        deposit_id: u64,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<String> {
+        let start = from_index.unwrap_or(0) as usize;
+        let limit = limit.map_or(MAX_VIEW_LIMIT, |limit| self.normalized_limit(Some(limit)));
         self.deposit_intents
+            .get(&deposit_id)
+            .map(|set| set.iter().skip(start).take(limit).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn get_deposit_active_intents(&self, deposit_id: u64) -> Vec<String> {
+        self.deposit_active_intents
             .get(&deposit_id)
             .map(|set| set.to_vec())
-            .unwrap_or_else(|| vec![])
+            .unwrap_or_default()
+    }
+
+    pub fn get_bitcoin_network(&self) -> BitcoinNetwork {
+        self.bitcoin_network
+    }
+
+    pub fn get_intent_retention_ms(&self) -> u64 {
+        self.intent_retention_ms
     }
 
     pub fn get_deposit_funding_v2(&self, deposit_id: u64) -> Option<DepositFundingMeta> {
@@ -1336,26 +2926,96 @@ impl Contract {
             .collect()
     }
 
-    pub fn get_deposits_by_funding_status_v2(
+    /// Quotes in `currency_code` from `limit` open deposits of `asset_id`,
+    /// starting at `from_index` of the open deposit set, cheapest first.
+    /// Pegged rates move with their feeds, so the set is not kept in price
+    /// order: callers merge pages until `next_index` is `None` for the full
+    /// book. `amount` keeps only listings that can fill it, `platform` filters
+    /// by payment platform and `buyer` drops deposits the buyer may not signal
+    /// on; without a buyer, allowlisted and reputation-gated deposits are left out.
+    pub fn get_order_book(
         &self,
-        status: FundingStatus,
+        asset_id: String,
+        currency_code: String,
+        platform: Option<String>,
+        amount: Option<U128>,
+        buyer: Option<AccountId>,
         from_index: Option<u64>,
         limit: Option<u64>,
-    ) -> Vec<u64> {
+    ) -> OrderBookPage {
         let start = from_index.unwrap_or(0) as usize;
         let limit = self.normalized_limit(limit);
+        let currency_code = currency_code.trim().to_uppercase();
+        let platform = platform.map(|platform| Self::payment_method_key(&platform));
+        let Some(open_deposits) = self.open_deposits_by_asset.get(&asset_id) else {
+            return OrderBookPage {
+                entries: Vec::new(),
+                next_index: None,
+            };
+        };
+        let deposit_ids: Vec<u64> = open_deposits.iter().skip(start).take(limit).collect();
+        let end = start + deposit_ids.len();
+
+        OrderBookPage {
+            entries: self.sorted_quotes(
+                deposit_ids,
+                &currency_code,
+                platform.as_deref(),
+                amount.map(|amount| amount.0),
+                buyer.as_ref(),
+            ),
+            next_index: (end < open_deposits.len() as usize).then_some(end as u64),
+        }
+    }
 
-        let mut matched = Vec::new();
+    /// The cheapest listing able to fill `amount` of `asset_id` in
+    /// `currency_code` for `buyer`. Only the first `MAX_ORDER_BOOK_SCAN` open
+    /// deposits are considered; page through `get_order_book` for larger books.
+    pub fn get_best_quote(
+        &self,
+        asset_id: String,
+        currency_code: String,
+        amount: U128,
+        platform: Option<String>,
+        buyer: Option<AccountId>,
+    ) -> Option<OrderBookEntry> {
+        let currency_code = currency_code.trim().to_uppercase();
+        let platform = platform.map(|platform| Self::payment_method_key(&platform));
+        let deposit_ids: Vec<u64> = self
+            .open_deposits_by_asset
+            .get(&asset_id)
+            .map(|set| set.iter().take(MAX_ORDER_BOOK_SCAN).collect())
+            .unwrap_or_default();
 
-        for deposit_id in 1..=self.deposit_counter {
-            if let Some(meta) = self.deposit_funding.get(&deposit_id) {
-                if meta.status == status {
-                    matched.push(deposit_id);
-                }
-            }
-        }
+        self.sorted_quotes(
+            deposit_ids,
+            &currency_code,
+            platform.as_deref(),
+            Some(amount.0),
+            buyer.as_ref(),
+        )
+        .into_iter()
+        .next()
+    }
 
-        matched.into_iter().skip(start).take(limit).collect()
+    /// V2 deposits in `status`. Unavailable after an upgrade until
+    /// `backfill_funding_status_index` has indexed the older deposits.
+    pub fn get_deposits_by_funding_status_v2(
+        &self,
+        status: FundingStatus,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<u64> {
+        assert!(
+            self.funding_status_backfill_next > self.funding_status_backfill_last,
+            "Funding status index is still being backfilled"
+        );
+        let start = from_index.unwrap_or(0) as usize;
+        let limit = self.normalized_limit(limit);
+        self.deposits_by_funding_status
+            .get(&status)
+            .map(|set| set.iter().skip(start).take(limit).collect())
+            .unwrap_or_default()
     }
 
     pub fn get_v2_config(&self) -> V2Config {
@@ -1375,6 +3035,194 @@ impl Contract {
         self.intent_attestations.get(&intent_hash)
     }
 
+    pub fn get_deposit_buyer_policy(&self, deposit_id: u64) -> BuyerPolicy {
+        self.deposit_buyer_policies
+            .get(&deposit_id)
+            .unwrap_or(BuyerPolicy::Open)
+    }
+
+    pub fn get_deposit_buyer_allowlist(
+        &self,
+        deposit_id: u64,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<AccountId> {
+        let start = from_index.unwrap_or(0) as usize;
+        let limit = self.normalized_limit(limit);
+        self.deposit_buyer_allowlists
+            .get(&deposit_id)
+            .map(|set| set.iter().skip(start).take(limit).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn get_account_stats(&self, account_id: AccountId) -> AccountStats {
+        self.account_stats.get(&account_id).unwrap_or_default()
+    }
+
+    pub fn get_deposit_min_buyer_fulfilled_intents(&self, deposit_id: u64) -> u64 {
+        self.deposit_min_buyer_fulfilled_intents
+            .get(&deposit_id)
+            .unwrap_or(0)
+    }
+
+    pub fn get_evm_token(&self, asset_id: String) -> Option<EvmToken> {
+        self.evm_tokens.get(&asset_id)
+    }
+
+    pub fn get_evm_tokens(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<EvmToken> {
+        let start = from_index.unwrap_or(0) as usize;
+        let limit = self.normalized_limit(limit);
+        self.evm_token_ids
+            .iter()
+            .skip(start)
+            .take(limit)
+            .filter_map(|asset_id| self.evm_tokens.get(&asset_id))
+            .collect()
+    }
+
+    /// Builds the unsigned EIP-1559 payout of a fulfilled or released intent
+    /// whose deposit token is a registered EVM token.
+    pub fn get_intent_evm_payout(
+        &self,
+        intent_hash: String,
+        nonce: u64,
+        max_priority_fee_per_gas: U128,
+        max_fee_per_gas: U128,
+        gas_limit: u64,
+    ) -> EvmPayoutView {
+        let intent = self.intents.get(&intent_hash).expect("Intent not found");
+        let payable = match intent.status {
+            IntentStatus::Released => true,
+            IntentStatus::Fulfilled => self.intent_payout_release_at_ms.get(&intent_hash).is_none(),
+            _ => false,
+        };
+        assert!(payable, "Intent payout is not releasable");
+        let deposit = self.deposits.get(&intent.deposit_id).unwrap();
+        let token = self
+            .evm_tokens
+            .get(&deposit.token)
+            .unwrap_or_else(|| env::panic_str("Deposit token is not a registered EVM token"));
+        assert!(
+            Self::evm_chain_id(&intent.chain) == Some(token.chain_id),
+            "Intent chain does not match the EVM token's chain"
+        );
+        let token_amount = Self::evm_token_amount(intent.amount, &token);
+
+        let params = ethereum::Eip1559Params {
+            chain_id: token.chain_id,
+            nonce: ethereum::U256::from_u64(nonce),
+            max_priority_fee_per_gas: ethereum::U256::from_u128(max_priority_fee_per_gas.0),
+            max_fee_per_gas: ethereum::U256::from_u128(max_fee_per_gas.0),
+            gas_limit: ethereum::U256::from_u64(gas_limit),
+        };
+        let tx = ethereum::build_payout_transaction(
+            &params,
+            token.contract_address.as_deref(),
+            &intent.recipient,
+            ethereum::U256::from_u128(token_amount),
+        );
+
+        EvmPayoutView {
+            intent_hash,
+            asset_id: token.asset_id,
+            chain_id: token.chain_id,
+            token_contract: token.contract_address,
+            recipient: intent.recipient,
+            amount: U128(intent.amount),
+            token_amount: U128(token_amount),
+            signing_payload_hex: hex::encode(ethereum::eip1559_signing_payload(&tx)),
+            signing_hash_hex: hex::encode(ethereum::eip1559_signing_hash(&tx)),
+        }
+    }
+
+    pub fn get_intent_group(&self, group_id: String) -> Option<IntentGroup> {
+        self.intent_groups.get(&group_id)
+    }
+
+    pub fn get_intent_group_intents(&self, group_id: String) -> Vec<Intent> {
+        self.intent_groups
+            .get(&group_id)
+            .map(|group| {
+                group
+                    .intent_hashes
+                    .iter()
+                    .filter_map(|intent_hash| self.intents.get(intent_hash))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn get_intent_dispute(&self, intent_hash: String) -> Option<IntentDispute> {
+        self.intent_disputes.get(&intent_hash)
+    }
+
+    pub fn get_intent_payout_release_at_ms(&self, intent_hash: String) -> Option<u64> {
+        self.intent_payout_release_at_ms.get(&intent_hash)
+    }
+
+    pub fn get_dispute_config(&self) -> DisputeConfig {
+        DisputeConfig {
+            arbiter_account_id: self.arbiter(),
+            dispute_window_ms: self.dispute_window_ms,
+        }
+    }
+
+    pub fn get_intent_limits_config(&self) -> IntentLimitsConfig {
+        IntentLimitsConfig {
+            max_intents_per_buyer: self.max_intents_per_buyer,
+            intent_cancel_cooldown_ms: self.intent_cancel_cooldown_ms,
+            min_intent_collateral_yocto: U128(self.min_intent_collateral_yocto),
+            intent_expiration_period: self.intent_expiration_period,
+        }
+    }
+
+    pub fn get_buyer_open_intent_count(&self, account_id: AccountId) -> u16 {
+        self.buyer_open_intent_counts.get(&account_id).unwrap_or(0)
+    }
+
+    pub fn get_buyer_deposit_cooldown_until_ms(
+        &self,
+        account_id: AccountId,
+        deposit_id: u64,
+    ) -> Option<u64> {
+        self.buyer_deposit_cooldowns
+            .get(&Self::buyer_deposit_cooldown_key(&account_id, deposit_id))
+            .filter(|until_ms| *until_ms > self.now_ms())
+    }
+
+    pub fn get_intent_collateral(&self, intent_hash: String) -> U128 {
+        U128(self.intent_collateral.get(&intent_hash).unwrap_or(0))
+    }
+
+    pub fn get_intent_payee_reveal(&self, intent_hash: String) -> Option<PayeeDetailsReveal> {
+        self.intent_payee_reveals.get(&intent_hash)
+    }
+
+    pub fn get_deposit_payment_method_data(
+        &self,
+        deposit_id: u64,
+    ) -> Vec<DepositPaymentMethodData> {
+        self.deposit_payment_method_data
+            .get(&deposit_id)
+            .unwrap_or_default()
+    }
+
+    pub fn get_deposit_price_pegs(&self, deposit_id: u64) -> Vec<DepositPricePeg> {
+        self.deposit_price_pegs.get(&deposit_id).unwrap_or_default()
+    }
+
+    pub fn get_price_feed(&self, feed_id: String) -> Option<PriceFeed> {
+        self.price_feeds.get(&feed_id)
+    }
+
+    pub fn get_price_max_staleness_ms(&self) -> u64 {
+        self.price_max_staleness_ms
+    }
+
+    pub fn get_attestation_amount_tolerance_bps(&self) -> u16 {
+        self.attestation_amount_tolerance_bps
+    }
+
     // === ADMIN FUNCTIONS ===
 
     pub fn set_protocol_fee(&mut self, fee: u128) {
@@ -1416,93 +3264,692 @@ impl Contract {
             Self::normalize_attestation_public_key_hex(public_key_hex);
     }
 
-    // === INTERNAL FUNCTIONS ===
-
-    fn sign_transaction(&self, intent: Intent) -> Promise {
-        env::log_str(&format!(
-            "Requesting MPC signature for {} on {} via {}",
-            intent.amount, intent.chain, MPC_CONTRACT_ID
-        ));
+    pub fn set_price_max_staleness_ms(&mut self, price_max_staleness_ms: u64) {
+        self.assert_owner();
+        assert!(
+            price_max_staleness_ms > 0,
+            "price_max_staleness_ms must be > 0"
+        );
+        self.price_max_staleness_ms = price_max_staleness_ms;
+    }
 
-        Promise::new(env::current_account_id())
+    pub fn set_attestation_amount_tolerance_bps(&mut self, tolerance_bps: u16) {
+        self.assert_owner();
+        assert!(
+            tolerance_bps <= MAX_ATTESTATION_AMOUNT_TOLERANCE_BPS,
+            "Tolerance cannot exceed 10%"
+        );
+        self.attestation_amount_tolerance_bps = tolerance_bps;
     }
 
-    fn assert_owner(&self) {
-        assert_eq!(env::predecessor_account_id(), self.owner_id, "Owner only");
+    pub fn register_evm_token(
+        &mut self,
+        asset_id: String,
+        chain_id: u64,
+        contract_address: Option<String>,
+        decimals: u8,
+        deposit_decimals: u8,
+        symbol: String,
+    ) {
+        self.assert_owner();
+        let asset_id = asset_id.trim().to_string();
+        assert!(!asset_id.is_empty(), "asset_id is required");
+        assert!(chain_id > 0, "chain_id must be > 0");
+        let contract_address = contract_address.map(|address| {
+            let address = address.trim().to_lowercase();
+            assert!(
+                ethereum::parse_address(&address).is_some(),
+                "contract_address must be a 0x-prefixed 20-byte address"
+            );
+            address
+        });
+
+        let token = EvmToken {
+            asset_id: asset_id.clone(),
+            chain_id,
+            contract_address,
+            decimals,
+            deposit_decimals,
+            symbol: symbol.trim().to_string(),
+        };
+        self.evm_tokens.insert(&asset_id, &token);
+        self.evm_token_ids.insert(&asset_id);
+
+        env::log_str(&format!("EVM token registered: {}", asset_id));
     }
 
-    fn assert_oracle(&self) {
-        assert_eq!(
-            env::predecessor_account_id(),
-            self.oracle_account_id,
-            "Oracle only"
+    pub fn remove_evm_token(&mut self, asset_id: String) {
+        self.assert_owner();
+        assert!(
+            self.evm_tokens.remove(&asset_id).is_some(),
+            "EVM token not found"
         );
+        self.evm_token_ids.remove(&asset_id);
+
+        env::log_str(&format!("EVM token removed: {}", asset_id));
     }
 
-    fn now_ms(&self) -> u64 {
-        env::block_timestamp_ms()
+    pub fn set_arbiter_account_id(&mut self, arbiter_account_id: Option<AccountId>) {
+        self.assert_owner();
+        self.arbiter_account_id = arbiter_account_id;
     }
 
-    fn insert_account_deposit(&mut self, account_id: &AccountId, deposit_id: u64) {
-        let mut user_deposits = self
-            .account_deposits
-            .get(account_id)
-            .unwrap_or_else(|| UnorderedSet::new(format!("ud:{}", account_id).as_bytes()));
-        user_deposits.insert(&deposit_id);
-        self.account_deposits.insert(account_id, &user_deposits);
+    pub fn set_bitcoin_network(&mut self, bitcoin_network: BitcoinNetwork) {
+        self.assert_owner();
+        self.bitcoin_network = bitcoin_network;
     }
 
-    fn is_deposit_manager(&self, caller: &AccountId, deposit: &Deposit) -> bool {
-        deposit.depositor == *caller || deposit.delegate.as_ref() == Some(caller)
+    pub fn set_dispute_window_ms(&mut self, dispute_window_ms: u64) {
+        self.assert_owner();
+        self.dispute_window_ms = dispute_window_ms;
     }
 
-    fn open_set_key(asset_id: &str) -> Vec<u8> {
-        let mut prefix = b"oa:".to_vec();
-        prefix.extend(env::sha256(asset_id.as_bytes()));
-        prefix
+    /// Adds up to `limit` deposits created before the upgrade to the funding
+    /// status index. Returns the next deposit id to backfill.
+    pub fn backfill_funding_status_index(&mut self, limit: Option<u64>) -> u64 {
+        self.assert_owner();
+        let limit = self.normalized_limit(limit) as u64;
+        let end = self
+            .funding_status_backfill_last
+            .min(self.funding_status_backfill_next + limit - 1);
+
+        for deposit_id in self.funding_status_backfill_next..=end {
+            if let Some(funding) = self.deposit_funding.get(&deposit_id) {
+                self.index_funding_status(deposit_id, &funding.status);
+            }
+        }
+        self.funding_status_backfill_next = self.funding_status_backfill_next.max(end + 1);
+
+        env::log_str(&format!(
+            "Funding status index backfilled through deposit {}",
+            end
+        ));
+        self.funding_status_backfill_next
     }
 
-    fn add_open_listing(&mut self, asset_id: &str, deposit_id: u64) {
-        let mut set = self
-            .open_deposits_by_asset
-            .get(&asset_id.to_string())
-            .unwrap_or_else(|| UnorderedSet::new(Self::open_set_key(asset_id)));
-        set.insert(&deposit_id);
-        self.open_deposits_by_asset
-            .insert(&asset_id.to_string(), &set);
+    pub fn set_intent_retention_ms(&mut self, intent_retention_ms: u64) {
+        self.assert_owner();
+        assert!(
+            intent_retention_ms >= self.intent_expiration_period / 1_000_000,
+            "intent_retention_ms must cover the intent expiration period"
+        );
+        self.intent_retention_ms = intent_retention_ms;
     }
 
-    fn remove_open_listing(&mut self, asset_id: &str, deposit_id: u64) {
-        if let Some(mut set) = self.open_deposits_by_asset.get(&asset_id.to_string()) {
-            set.remove(&deposit_id);
-            if set.is_empty() {
-                self.open_deposits_by_asset.remove(&asset_id.to_string());
-            } else {
-                self.open_deposits_by_asset
-                    .insert(&asset_id.to_string(), &set);
+    /// Rebuilds the active intent sets of up to `limit` deposits created before
+    /// active intents were tracked. Returns the next deposit id to rebuild.
+    pub fn backfill_deposit_active_intents(&mut self, limit: Option<u64>) -> u64 {
+        self.assert_owner();
+        let limit = self.normalized_limit(limit) as u64;
+        let end = self
+            .active_intents_backfill_last
+            .min(self.active_intents_backfill_next + limit - 1);
+
+        for deposit_id in self.active_intents_backfill_next..=end {
+            if self.deposit_intents.get(&deposit_id).is_some() {
+                self.store_deposit_active_intents(deposit_id);
             }
         }
+        self.active_intents_backfill_next = self.active_intents_backfill_next.max(end + 1);
+
+        env::log_str(&format!(
+            "Deposit active intents rebuilt through deposit {}",
+            end
+        ));
+        self.active_intents_backfill_next
     }
 
-    fn sync_open_listing_state(&mut self, deposit_id: u64, deposit: &Deposit) {
-        if let Some(funding) = self.deposit_funding.get(&deposit_id) {
-            if funding.status == FundingStatus::Funded && deposit.remaining_deposits > 0 {
-                self.add_open_listing(&funding.asset_id, deposit_id);
-            } else {
-                self.remove_open_listing(&funding.asset_id, deposit_id);
-            }
-        }
+    /// Rebuilds one deposit's active intent set from its history, to repair a
+    /// set that has drifted.
+    pub fn rebuild_deposit_active_intents(&mut self, deposit_id: u64) -> u64 {
+        self.assert_owner();
+        assert!(
+            self.deposit_intents.get(&deposit_id).is_some(),
+            "Deposit not found"
+        );
+        self.store_deposit_active_intents(deposit_id)
     }
 
-    fn build_deposit_summary_v2(&self, deposit_id: u64) -> Option<DepositSummaryV2> {
-        let deposit = self.deposits.get(&deposit_id)?;
-        let funding = self.deposit_funding.get(&deposit_id)?;
+    /// Caps the signaled intents a buyer may hold open at once. Zero removes the cap.
+    pub fn set_max_intents_per_buyer(&mut self, max: u16) {
+        self.assert_owner();
+        self.max_intents_per_buyer = max;
+    }
 
-        if funding.status != FundingStatus::Funded || deposit.remaining_deposits == 0 {
-            return None;
-        }
+    pub fn set_intent_cancel_cooldown_ms(&mut self, cooldown_ms: u64) {
+        self.assert_owner();
+        self.intent_cancel_cooldown_ms = cooldown_ms;
+    }
 
-        Some(DepositSummaryV2 {
+    pub fn set_min_intent_collateral_yocto(&mut self, collateral: U128) {
+        self.assert_owner();
+        self.min_intent_collateral_yocto = collateral.0;
+    }
+
+    // === INTERNAL FUNCTIONS ===
+
+    /// Fulfills a signaled intent, counting it in account stats only when
+    /// `attested`.
+    fn complete_fulfillment(&mut self, intent_hash: String, attested: bool) -> PromiseOrValue<()> {
+        let mut intent = self.intents.get(&intent_hash).expect("Intent not found");
+        assert!(
+            intent.status == IntentStatus::Signaled,
+            "Intent not in signaled state"
+        );
+
+        intent.status = IntentStatus::Fulfilled;
+        self.intents.insert(&intent_hash, &intent);
+
+        let mut deposit = self.deposits.get(&intent.deposit_id).unwrap();
+        deposit.outstanding_intents -= intent.amount;
+        self.deposits.insert(&intent.deposit_id, &deposit);
+        self.close_active_intent(&intent);
+        if attested {
+            let time_to_fulfill_ms = self.intent_age_ms(&intent);
+            self.update_intent_stats(&intent, &deposit.depositor, |stats| {
+                stats.record_fulfillment(&deposit.token, intent.amount, time_to_fulfill_ms)
+            });
+        }
+
+        env::log_str(&format!("Intent fulfilled: {}", intent_hash));
+        if self.dispute_window_ms == 0 {
+            self.release_intent_collateral(&intent_hash, &intent.buyer);
+            return PromiseOrValue::Promise(self.sign_transaction(intent));
+        }
+
+        let release_at_ms = self.now_ms() + self.dispute_window_ms;
+        self.intent_payout_release_at_ms
+            .insert(&intent_hash, &release_at_ms);
+        self.intent_fulfilled_at_ms
+            .insert(&intent_hash, &self.now_ms());
+        env::log_str(&format!(
+            "Intent payout held: {} until {}",
+            intent_hash, release_at_ms
+        ));
+        PromiseOrValue::Value(())
+    }
+
+    /// Applies `update` to the buyer side of the buyer's stats and the seller
+    /// side of the depositor's stats.
+    fn update_intent_stats<F>(&mut self, intent: &Intent, depositor: &AccountId, update: F)
+    where
+        F: Fn(&mut IntentStats),
+    {
+        let mut buyer_stats = self.account_stats.get(&intent.buyer).unwrap_or_default();
+        update(&mut buyer_stats.buyer);
+        self.account_stats.insert(&intent.buyer, &buyer_stats);
+
+        let mut seller_stats = self.account_stats.get(depositor).unwrap_or_default();
+        update(&mut seller_stats.seller);
+        self.account_stats.insert(depositor, &seller_stats);
+    }
+
+    fn is_intent_expired(&self, intent: &Intent) -> bool {
+        env::block_timestamp() >= intent.timestamp + self.intent_expiration_period
+    }
+
+    fn intent_age_ms(&self, intent: &Intent) -> u64 {
+        self.now_ms().saturating_sub(intent.timestamp / 1_000_000)
+    }
+
+    /// Time since the intent became terminal, or since it was signaled for
+    /// intents closed before closing times were recorded.
+    fn intent_closed_age_ms(&self, intent: &Intent) -> u64 {
+        match self.intent_closed_at_ms.get(&intent.intent_hash) {
+            Some(closed_at_ms) => self.now_ms().saturating_sub(closed_at_ms),
+            None => self.intent_age_ms(intent),
+        }
+    }
+
+    fn arbiter(&self) -> AccountId {
+        self.arbiter_account_id
+            .clone()
+            .unwrap_or_else(|| self.owner_id.clone())
+    }
+
+    /// Appends validated sha256 hex `evidence_hashes` to `existing`, skipping duplicates.
+    fn normalize_evidence_hashes(
+        evidence_hashes: Vec<String>,
+        existing: Vec<String>,
+    ) -> Vec<String> {
+        let mut normalized = existing;
+        for hash in evidence_hashes {
+            let hash = hash.trim().to_lowercase();
+            assert!(
+                decode_hex_fixed::<32>(&hash).is_some(),
+                "evidence hash must be 32-byte hex"
+            );
+            if !normalized.contains(&hash) {
+                normalized.push(hash);
+            }
+        }
+        assert!(
+            normalized.len() <= MAX_DISPUTE_EVIDENCE,
+            "Too many dispute evidence hashes"
+        );
+        normalized
+    }
+
+    /// Attached yoctoNEAR beyond the storage deposit of `intents` intents,
+    /// held as collateral.
+    fn attached_collateral(intents: usize) -> u128 {
+        env::attached_deposit()
+            .as_yoctonear()
+            .saturating_sub(INTENT_STORAGE_DEPOSIT_YOCTO * intents as u128)
+    }
+
+    fn buyer_deposit_cooldown_key(buyer: &AccountId, deposit_id: u64) -> String {
+        format!("{}:{}", buyer, deposit_id)
+    }
+
+    fn is_bitcoin_chain(chain: &str) -> bool {
+        matches!(chain.trim().to_lowercase().as_str(), "btc" | "bitcoin")
+    }
+
+    /// EIP-155 chain id of a supported EVM chain name.
+    fn evm_chain_id(chain: &str) -> Option<u64> {
+        match chain.trim().to_lowercase().as_str() {
+            "eth" | "ethereum" => Some(1),
+            "op" | "optimism" => Some(10),
+            "bsc" | "bnb" => Some(56),
+            "polygon" | "matic" => Some(137),
+            "base" => Some(8453),
+            "arb" | "arbitrum" => Some(42161),
+            "avax" | "avalanche" => Some(43114),
+            _ => None,
+        }
+    }
+
+    /// Rescales an intent amount from `deposit_decimals` to the token's EVM
+    /// `decimals`, refusing conversions that overflow or drop precision.
+    fn evm_token_amount(amount: u128, token: &EvmToken) -> u128 {
+        if token.decimals >= token.deposit_decimals {
+            10u128
+                .checked_pow((token.decimals - token.deposit_decimals) as u32)
+                .and_then(|scale| amount.checked_mul(scale))
+                .expect("EVM token amount overflow")
+        } else {
+            let scale = 10u128
+                .checked_pow((token.deposit_decimals - token.decimals) as u32)
+                .expect("EVM token amount overflow");
+            assert!(
+                amount.is_multiple_of(scale),
+                "Intent amount is not representable in the EVM token's decimals"
+            );
+            amount / scale
+        }
+    }
+
+    fn remove_intent_from_group(&mut self, intent_hash: &String) {
+        let Some(group_id) = self.intent_group_ids.remove(intent_hash) else {
+            return;
+        };
+        let Some(mut group) = self.intent_groups.get(&group_id) else {
+            return;
+        };
+        group.intent_hashes.retain(|hash| hash != intent_hash);
+        if group.intent_hashes.is_empty() {
+            self.intent_groups.remove(&group_id);
+        } else {
+            self.intent_groups.insert(&group_id, &group);
+        }
+    }
+
+    fn is_intent_collectable(&self, intent: &Intent) -> bool {
+        let terminal = match intent.status {
+            IntentStatus::Cancelled
+            | IntentStatus::Released
+            | IntentStatus::Expired
+            | IntentStatus::Rejected => true,
+            IntentStatus::Fulfilled => self
+                .intent_payout_release_at_ms
+                .get(&intent.intent_hash)
+                .is_none(),
+            IntentStatus::Signaled | IntentStatus::PendingApproval | IntentStatus::Disputed => {
+                false
+            }
+        };
+        terminal && self.intent_closed_age_ms(intent) >= self.intent_retention_ms
+    }
+
+    fn is_deposit_active_set_ready(&self, deposit_id: u64) -> bool {
+        deposit_id < self.active_intents_backfill_next
+            || deposit_id > self.active_intents_backfill_last
+    }
+
+    /// Replaces the deposit's active intent set with the active intents in its
+    /// history and returns their count.
+    fn store_deposit_active_intents(&mut self, deposit_id: u64) -> u64 {
+        let mut active_intents = self
+            .deposit_active_intents
+            .get(&deposit_id)
+            .unwrap_or_else(|| UnorderedSet::new(format!("da:{}", deposit_id).as_bytes()));
+        active_intents.clear();
+        for intent_hash in self.history_active_intent_hashes(deposit_id) {
+            active_intents.insert(&intent_hash);
+        }
+        let count = active_intents.len();
+        self.deposit_active_intents
+            .insert(&deposit_id, &active_intents);
+        count
+    }
+
+    fn history_active_intent_hashes(&self, deposit_id: u64) -> Vec<String> {
+        let Some(history) = self.deposit_intents.get(&deposit_id) else {
+            return Vec::new();
+        };
+        history
+            .iter()
+            .filter(|intent_hash| {
+                self.intents
+                    .get(intent_hash)
+                    .is_some_and(|intent| match intent.status {
+                        IntentStatus::Signaled => true,
+                        IntentStatus::Disputed => self
+                            .intent_disputes
+                            .get(intent_hash)
+                            .is_some_and(|dispute| dispute.status_before == IntentStatus::Signaled),
+                        _ => false,
+                    })
+            })
+            .collect()
+    }
+
+    fn insert_deposit_active_intent(&mut self, deposit_id: u64, intent_hash: &String) {
+        let mut active_intents = self
+            .deposit_active_intents
+            .get(&deposit_id)
+            .unwrap_or_else(|| UnorderedSet::new(format!("da:{}", deposit_id).as_bytes()));
+        active_intents.insert(intent_hash);
+        self.deposit_active_intents
+            .insert(&deposit_id, &active_intents);
+    }
+
+    /// Asserts the deposit can lock another intent under `max_intents_per_deposit`.
+    fn assert_deposit_intent_slot(&self, deposit_id: u64) {
+        assert!(
+            self.is_deposit_active_set_ready(deposit_id),
+            "Deposit active intents are still being rebuilt"
+        );
+        let active_intents = self
+            .deposit_active_intents
+            .get(&deposit_id)
+            .map_or(0, |set| set.len());
+        assert!(
+            active_intents < self.max_intents_per_deposit as u64,
+            "Max intents reached"
+        );
+    }
+
+    /// Blocks the buyer from signaling on the deposit for `intent_cancel_cooldown_ms`.
+    fn start_buyer_deposit_cooldown(&mut self, buyer: &AccountId, deposit_id: u64) {
+        if self.intent_cancel_cooldown_ms > 0 {
+            self.buyer_deposit_cooldowns.insert(
+                &Self::buyer_deposit_cooldown_key(buyer, deposit_id),
+                &(self.now_ms() + self.intent_cancel_cooldown_ms),
+            );
+        }
+    }
+
+    /// Counts a new signaled or pending intent against the buyer's `max_intents_per_buyer`.
+    fn open_buyer_intent_slot(&mut self, buyer: &AccountId) {
+        let open_intents = self.buyer_open_intent_counts.get(buyer).unwrap_or(0);
+        assert!(
+            self.max_intents_per_buyer == 0 || open_intents < self.max_intents_per_buyer,
+            "Max intents per buyer reached"
+        );
+        self.buyer_open_intent_counts
+            .insert(buyer, &(open_intents + 1));
+    }
+
+    /// Frees the intent's deposit and buyer slots and records when it closed.
+    fn close_active_intent(&mut self, intent: &Intent) {
+        self.intent_closed_at_ms
+            .insert(&intent.intent_hash, &self.now_ms());
+        if let Some(mut active_intents) = self.deposit_active_intents.get(&intent.deposit_id) {
+            active_intents.remove(&intent.intent_hash);
+            self.deposit_active_intents
+                .insert(&intent.deposit_id, &active_intents);
+        }
+
+        let open_intents = self
+            .buyer_open_intent_counts
+            .get(&intent.buyer)
+            .unwrap_or(0);
+        if open_intents > 1 {
+            self.buyer_open_intent_counts
+                .insert(&intent.buyer, &(open_intents - 1));
+        } else {
+            self.buyer_open_intent_counts.remove(&intent.buyer);
+        }
+    }
+
+    /// Pays out any collateral held for the intent to `recipient`.
+    fn release_intent_collateral(&mut self, intent_hash: &String, recipient: &AccountId) {
+        if let Some(collateral) = self.intent_collateral.remove(intent_hash) {
+            Promise::new(recipient.clone()).transfer(NearToken::from_yoctonear(collateral));
+        }
+    }
+
+    fn sign_transaction(&self, intent: Intent) -> Promise {
+        env::log_str(&format!(
+            "Requesting MPC signature for {} on {} via {}",
+            intent.amount, intent.chain, MPC_CONTRACT_ID
+        ));
+
+        Promise::new(env::current_account_id())
+    }
+
+    fn migrate_v4_state(state_bytes: &[u8]) -> Self {
+        if let Ok(current) = Contract::try_from_slice(state_bytes) {
+            return current;
+        }
+
+        let previous = V3Contract::try_from_slice(state_bytes)
+            .unwrap_or_else(|_| env::panic_str("Unsupported contract state for migrate_v4"));
+
+        Self {
+            owner_id: previous.owner_id,
+            deposit_counter: previous.deposit_counter,
+            deposits: previous.deposits,
+            account_deposits: previous.account_deposits,
+            deposit_intents: previous.deposit_intents,
+            intent_counter: previous.intent_counter,
+            intents: previous.intents,
+            account_intents: previous.account_intents,
+            payment_methods: previous.payment_methods,
+            protocol_fee: previous.protocol_fee,
+            protocol_fee_recipient: previous.protocol_fee_recipient,
+            max_intents_per_deposit: previous.max_intents_per_deposit,
+            intent_expiration_period: previous.intent_expiration_period,
+            deposit_funding: previous.deposit_funding,
+            open_deposits_by_asset: previous.open_deposits_by_asset,
+            oracle_account_id: previous.oracle_account_id,
+            v2_storage_fee_yocto: previous.v2_storage_fee_yocto,
+            topup_window_ms: previous.topup_window_ms,
+            max_quote_rotations: previous.max_quote_rotations,
+            used_attestation_sessions: previous.used_attestation_sessions,
+            intent_attestations: previous.intent_attestations,
+            attestation_public_key_hex: previous.attestation_public_key_hex,
+            attestation_amount_tolerance_bps: 0,
+            deposit_payment_method_data: LookupMap::new(b"c"),
+            intent_conversion_rates: LookupMap::new(b"r"),
+            deposit_price_pegs: LookupMap::new(b"g"),
+            price_feeds: LookupMap::new(b"q"),
+            price_max_staleness_ms: DEFAULT_PRICE_MAX_STALENESS_MS,
+            payment_method_server_names: LookupMap::new(b"n"),
+            payment_method_names: UnorderedSet::new(b"m"),
+            intent_payee_reveals: LookupMap::new(b"x"),
+            max_intents_per_buyer: 0,
+            intent_cancel_cooldown_ms: 0,
+            min_intent_collateral_yocto: 0,
+            buyer_open_intent_counts: LookupMap::new(b"h"),
+            buyer_deposit_cooldowns: LookupMap::new(b"k"),
+            intent_collateral: LookupMap::new(b"l"),
+            deposit_buyer_policies: LookupMap::new(b"j"),
+            deposit_buyer_allowlists: LookupMap::new(b"v"),
+            arbiter_account_id: None,
+            dispute_window_ms: 0,
+            intent_disputes: LookupMap::new(b"y"),
+            intent_payout_release_at_ms: LookupMap::new(b"z"),
+            intent_fulfilled_at_ms: LookupMap::new(b"F"),
+            account_stats: LookupMap::new(b"S"),
+            deposit_min_buyer_fulfilled_intents: LookupMap::new(b"R"),
+            deposit_active_intents: LookupMap::new(b"D"),
+            intent_retention_ms: DEFAULT_INTENT_RETENTION_MS,
+            intent_closed_at_ms: LookupMap::new(b"C"),
+            deposits_by_funding_status: LookupMap::new(b"I"),
+            funding_status_backfill_next: 1,
+            funding_status_backfill_last: previous.deposit_counter,
+            active_intents_backfill_next: 1,
+            active_intents_backfill_last: previous.deposit_counter,
+            intent_group_counter: 0,
+            intent_groups: LookupMap::new(b"G"),
+            intent_group_ids: LookupMap::new(b"H"),
+            evm_tokens: LookupMap::new(b"E"),
+            evm_token_ids: UnorderedSet::new(b"T"),
+            bitcoin_network: BitcoinNetwork::Testnet,
+        }
+    }
+
+    fn assert_owner(&self) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Owner only");
+    }
+
+    fn assert_oracle(&self) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.oracle_account_id,
+            "Oracle only"
+        );
+    }
+
+    fn now_ms(&self) -> u64 {
+        env::block_timestamp_ms()
+    }
+
+    fn insert_account_deposit(&mut self, account_id: &AccountId, deposit_id: u64) {
+        let mut user_deposits = self
+            .account_deposits
+            .get(account_id)
+            .unwrap_or_else(|| UnorderedSet::new(format!("ud:{}", account_id).as_bytes()));
+        user_deposits.insert(&deposit_id);
+        self.account_deposits.insert(account_id, &user_deposits);
+    }
+
+    fn is_allowlisted_buyer(&self, deposit_id: u64, buyer: &AccountId) -> bool {
+        self.deposit_buyer_allowlists
+            .get(&deposit_id)
+            .is_some_and(|allowlist| allowlist.contains(buyer))
+    }
+
+    /// Whether the deposit's buyer policy and reputation requirement let
+    /// `buyer` signal on it. Manual approval deposits accept any buyer.
+    fn buyer_may_signal(&self, deposit_id: u64, buyer: Option<&AccountId>) -> bool {
+        let policy_allows = match self.get_deposit_buyer_policy(deposit_id) {
+            BuyerPolicy::Allowlist => {
+                buyer.is_some_and(|buyer| self.is_allowlisted_buyer(deposit_id, buyer))
+            }
+            BuyerPolicy::Open | BuyerPolicy::ManualApproval => true,
+        };
+        let min_fulfilled = self
+            .deposit_min_buyer_fulfilled_intents
+            .get(&deposit_id)
+            .unwrap_or(0);
+        let fulfilled = buyer
+            .and_then(|buyer| self.account_stats.get(buyer))
+            .map_or(0, |stats| stats.buyer.fulfilled);
+        policy_allows && fulfilled >= min_fulfilled
+    }
+
+    fn is_deposit_manager(&self, caller: &AccountId, deposit: &Deposit) -> bool {
+        deposit.depositor == *caller || deposit.delegate.as_ref() == Some(caller)
+    }
+
+    fn open_set_key(asset_id: &str) -> Vec<u8> {
+        let mut prefix = b"oa:".to_vec();
+        prefix.extend(env::sha256(asset_id.as_bytes()));
+        prefix
+    }
+
+    fn add_open_listing(&mut self, asset_id: &str, deposit_id: u64) {
+        let mut set = self
+            .open_deposits_by_asset
+            .get(&asset_id.to_string())
+            .unwrap_or_else(|| UnorderedSet::new(Self::open_set_key(asset_id)));
+        set.insert(&deposit_id);
+        self.open_deposits_by_asset
+            .insert(&asset_id.to_string(), &set);
+    }
+
+    fn remove_open_listing(&mut self, asset_id: &str, deposit_id: u64) {
+        if let Some(mut set) = self.open_deposits_by_asset.get(&asset_id.to_string()) {
+            set.remove(&deposit_id);
+            if set.is_empty() {
+                self.open_deposits_by_asset.remove(&asset_id.to_string());
+            } else {
+                self.open_deposits_by_asset
+                    .insert(&asset_id.to_string(), &set);
+            }
+        }
+    }
+
+    /// Writes `funding` and moves the deposit between funding status index sets.
+    fn store_deposit_funding(&mut self, deposit_id: u64, funding: &DepositFundingMeta) {
+        if let Some(previous) = self.deposit_funding.get(&deposit_id) {
+            if previous.status != funding.status {
+                self.unindex_funding_status(deposit_id, &previous.status);
+            }
+        }
+        self.deposit_funding.insert(&deposit_id, funding);
+        self.index_funding_status(deposit_id, &funding.status);
+    }
+
+    fn funding_status_set_key(status: &FundingStatus) -> Vec<u8> {
+        let mut prefix = b"fs:".to_vec();
+        prefix.extend(borsh::to_vec(status).unwrap());
+        prefix
+    }
+
+    fn index_funding_status(&mut self, deposit_id: u64, status: &FundingStatus) {
+        let mut set = self
+            .deposits_by_funding_status
+            .get(status)
+            .unwrap_or_else(|| UnorderedSet::new(Self::funding_status_set_key(status)));
+        set.insert(&deposit_id);
+        self.deposits_by_funding_status.insert(status, &set);
+    }
+
+    fn unindex_funding_status(&mut self, deposit_id: u64, status: &FundingStatus) {
+        if let Some(mut set) = self.deposits_by_funding_status.get(status) {
+            set.remove(&deposit_id);
+            if set.is_empty() {
+                self.deposits_by_funding_status.remove(status);
+            } else {
+                self.deposits_by_funding_status.insert(status, &set);
+            }
+        }
+    }
+
+    fn sync_open_listing_state(&mut self, deposit_id: u64, deposit: &Deposit) {
+        if let Some(funding) = self.deposit_funding.get(&deposit_id) {
+            if funding.status == FundingStatus::Funded && deposit.remaining_deposits > 0 {
+                self.add_open_listing(&funding.asset_id, deposit_id);
+            } else {
+                self.remove_open_listing(&funding.asset_id, deposit_id);
+            }
+        }
+    }
+
+    fn build_deposit_summary_v2(&self, deposit_id: u64) -> Option<DepositSummaryV2> {
+        let deposit = self.deposits.get(&deposit_id)?;
+        let funding = self.deposit_funding.get(&deposit_id)?;
+
+        if funding.status != FundingStatus::Funded || deposit.remaining_deposits == 0 {
+            return None;
+        }
+
+        Some(DepositSummaryV2 {
             deposit_id,
             asset_id: funding.asset_id,
             depositor: deposit.depositor,
@@ -1519,19 +3966,110 @@ impl Contract {
         })
     }
 
+    /// Collects up to `limit` intents from `set` that match `filter`, scanning
+    /// at most `MAX_INTENT_SCAN` entries so large sets stay within view gas.
+    fn page_intents(
+        &self,
+        set: Option<UnorderedSet<String>>,
+        filter: IntentFilter,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> IntentPage {
+        let limit = self.normalized_limit(limit);
+        let Some(set) = set else {
+            return IntentPage {
+                intents: Vec::new(),
+                next_index: None,
+            };
+        };
+
+        let chain = filter.chain.map(|chain| chain.trim().to_lowercase());
+        let platform = filter
+            .platform
+            .map(|platform| Self::payment_method_key(&platform));
+        let hashes = set.as_vector();
+        let start = from_index.unwrap_or(0);
+        let end = hashes.len().min(start.saturating_add(MAX_INTENT_SCAN));
+
+        let mut intents = Vec::new();
+        let mut index = start;
+        while index < end && intents.len() < limit {
+            let intent_hash = hashes.get(index).unwrap();
+            index += 1;
+            let Some(intent) = self.intents.get(&intent_hash) else {
+                continue;
+            };
+            let timestamp_ms = intent.timestamp / 1_000_000;
+            let matches = filter
+                .statuses
+                .as_ref()
+                .is_none_or(|statuses| statuses.contains(&intent.status))
+                && chain
+                    .as_ref()
+                    .is_none_or(|chain| intent.chain.trim().to_lowercase() == *chain)
+                && platform.as_ref().is_none_or(|platform| {
+                    Self::parse_payment_method(&intent.payment_method).0 == *platform
+                })
+                && filter.from_ms.is_none_or(|from_ms| timestamp_ms >= from_ms)
+                && filter.to_ms.is_none_or(|to_ms| timestamp_ms < to_ms);
+            if matches {
+                intents.push(intent);
+            }
+        }
+
+        IntentPage {
+            intents,
+            next_index: (index < hashes.len()).then_some(index),
+        }
+    }
+
     fn normalized_limit(&self, limit: Option<u64>) -> usize {
         let raw = limit.unwrap_or(50) as usize;
         raw.clamp(1, MAX_VIEW_LIMIT)
     }
 
-    fn extract_payment_details(payment_methods: &[String]) -> Option<(String, String)> {
-        for raw in payment_methods {
+    /// Every entry names its payee either in plaintext (`platform::tagname`) or
+    /// by `payee_details_hash`, never both, and at least one entry is payable.
+    fn assert_payee_details(
+        payment_methods: &[String],
+        payment_method_data: Option<&[DepositPaymentMethodData]>,
+    ) {
+        let mut payable = false;
+        for (index, raw) in payment_methods.iter().enumerate() {
             let (platform, tagname) = Self::parse_payment_method(raw);
-            if !platform.is_empty() && !tagname.is_empty() {
-                return Some((platform, tagname));
-            }
+            let hashed = payment_method_data
+                .and_then(|data| data.get(index))
+                .is_some_and(|data| !data.payee_details_hash.is_empty());
+            assert!(
+                !hashed || tagname.is_empty(),
+                "payment method with payee_details_hash must not include a tagname"
+            );
+            payable |= !platform.is_empty() && (hashed || !tagname.is_empty());
         }
-        None
+        assert!(
+            payable,
+            "payment_methods must include platform::tagname or payee_details_hash"
+        );
+    }
+
+    fn intent_payee_details_hash(&self, intent: &Intent) -> Option<String> {
+        let deposit = self.deposits.get(&intent.deposit_id)?;
+        let index = deposit
+            .payment_methods
+            .iter()
+            .position(|candidate| candidate == &intent.payment_method)?;
+        self.deposit_payment_method_data
+            .get(&intent.deposit_id)?
+            .get(index)
+            .map(|data| data.payee_details_hash.clone())
+            .filter(|hash| !hash.is_empty())
+    }
+
+    fn compute_payee_details_hash(platform: &str, tagname: &str, salt: &str) -> String {
+        env::sha256(&payee_details_preimage(platform, tagname, salt))
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
     }
 
     fn parse_payment_method(raw: &str) -> (String, String) {
@@ -1547,74 +4085,2759 @@ impl Contract {
         (normalized.to_lowercase(), String::new())
     }
 
-    fn build_intent_transfer_memo(intent_hash: &str, deposit_id: u64) -> String {
-        let suffix = intent_hash.strip_prefix("intent:").unwrap_or(intent_hash);
-        format!("anypay:{}:{}", deposit_id, suffix)
+    fn registered_payment_method(&self, platform: &str) -> PaymentMethod {
+        let payment_method = self
+            .payment_methods
+            .get(&Self::payment_method_key(platform))
+            .expect("Payment method not registered");
+        assert!(payment_method.initialized, "Payment method not initialized");
+        payment_method
     }
 
-    fn build_attestation_canonical_message(payload: &AttestationPayload) -> String {
-        let intent_id = payload.intent_id.as_deref().unwrap_or("");
-        let expected_memo = payload.expected_memo.as_deref().unwrap_or("");
-        let expected_amount = payload.expected_amount.as_deref().unwrap_or("");
-        let expected_currency = payload.expected_currency.as_deref().unwrap_or("");
-        let expected_platform = payload.expected_platform.as_deref().unwrap_or("");
-        let expected_tagname = payload.expected_tagname.as_deref().unwrap_or("");
-
-        format!(
-            "version={}\nsession_id={}\nintent_id={}\nserver_name={}\nexpected_memo={}\nexpected_amount={}\nexpected_currency={}\nexpected_platform={}\nexpected_tagname={}\ntranscript_digest_sha256={}\nissued_at_ms={}\nexpires_at_ms={}\npolicy_passed={}\n",
-            ATTESTATION_VERSION,
-            Self::sanitize_attestation_value(&payload.session_id),
-            Self::sanitize_attestation_value(intent_id),
-            Self::sanitize_attestation_value(&payload.server_name),
-            Self::sanitize_attestation_value(expected_memo),
-            Self::sanitize_attestation_value(expected_amount),
-            Self::sanitize_attestation_value(expected_currency),
-            Self::sanitize_attestation_value(expected_platform),
-            Self::sanitize_attestation_value(expected_tagname),
-            Self::sanitize_attestation_value(&payload.transcript_digest_sha256),
-            payload.issued_at_ms,
-            payload.expires_at_ms,
-            payload.checks.policy_passed,
+    fn assert_payment_methods_registered(
+        &self,
+        payment_methods: &[String],
+        payment_method_data: Option<&[DepositPaymentMethodData]>,
+    ) {
+        for (index, raw) in payment_methods.iter().enumerate() {
+            let (platform, _) = Self::parse_payment_method(raw);
+            let registered = self.registered_payment_method(&platform);
+            if let Some(data) = payment_method_data.and_then(|data| data.get(index)) {
+                assert!(
+                    data.currencies
+                        .iter()
+                        .all(|currency| registered.currencies.contains(&currency.code)),
+                    "Currency not supported by payment method"
+                );
+            }
+        }
+    }
+
+    fn payment_method_key(name: &str) -> String {
+        name.trim().to_lowercase()
+    }
+
+    fn normalize_server_name(value: &str) -> String {
+        value.trim().trim_end_matches('.').to_lowercase()
+    }
+
+    fn store_payment_method_server_names(&mut self, name: &str, server_names: Vec<String>) {
+        let mut normalized: Vec<String> = Vec::with_capacity(server_names.len());
+        for server_name in server_names {
+            let server_name = Self::normalize_server_name(&server_name);
+            assert!(!server_name.is_empty(), "server name is required");
+            assert!(
+                !server_name.contains(|ch: char| ch.is_whitespace() || ch == '/' || ch == ':'),
+                "server name must be a bare host name"
+            );
+            if !normalized.contains(&server_name) {
+                normalized.push(server_name);
+            }
+        }
+
+        let key = Self::payment_method_key(name);
+        if normalized.is_empty() {
+            self.payment_method_server_names.remove(&key);
+        } else {
+            self.payment_method_server_names.insert(&key, &normalized);
+        }
+    }
+
+    /// A payment method without configured server names accepts any server,
+    /// as before the allowlist existed.
+    fn assert_attested_server_name_allowed(&self, platform: &str, server_name: &str) {
+        let Some(allowed_server_names) =
+            self.payment_method_server_names.get(&platform.to_string())
+        else {
+            return;
+        };
+        assert!(
+            allowed_server_names.contains(&Self::normalize_server_name(server_name)),
+            "attestation server name is not allowed for payment method"
+        );
+    }
+
+    fn build_intent_transfer_memo(intent_hash: &str, deposit_id: u64) -> String {
+        let suffix = intent_hash.strip_prefix("intent:").unwrap_or(intent_hash);
+        format!("anypay:{}:{}", deposit_id, suffix)
+    }
+
+    /// Intents signaled before rates were locked, or on deposits without
+    /// currency rates, only rely on the attestation's own `amount_match` check.
+    fn assert_attested_amount_covers_intent(&self, intent: &Intent, attested_amount: &str) {
+        let Some(conversion_rate) = self.intent_conversion_rates.get(&intent.intent_hash) else {
+            return;
+        };
+
+        let currency_code = intent.currency_code.trim().to_uppercase();
+        let decimals = Self::currency_decimals(&currency_code);
+        let attested_minor = Self::parse_fiat_amount(attested_amount, decimals)
+            .unwrap_or_else(|| env::panic_str("invalid attestation expected_amount"));
+        let due_minor = Self::fiat_amount_due(intent.amount, conversion_rate)
+            .unwrap_or_else(|| env::panic_str("fiat amount overflow"));
+
+        assert!(
+            attested_minor
+                >= Self::min_attested_amount(due_minor, self.attestation_amount_tolerance_bps),
+            "attestation amount below intent amount"
+        );
+    }
+
+    /// Smallest attested amount accepted for `due_minor`, `tolerance_bps` below it, rounded up.
+    fn min_attested_amount(due_minor: u128, tolerance_bps: u16) -> u128 {
+        due_minor
+            .saturating_mul(BPS_DENOMINATOR - tolerance_bps as u128)
+            .div_ceil(BPS_DENOMINATOR)
+    }
+
+    /// Fiat minor units owed for `amount` token base units, rounded up.
+    fn fiat_amount_due(amount: u128, conversion_rate: u128) -> Option<u128> {
+        let whole = (amount / PRECISE_UNIT).checked_mul(conversion_rate)?;
+        let fraction = (amount % PRECISE_UNIT)
+            .checked_mul(conversion_rate)?
+            .div_ceil(PRECISE_UNIT);
+        whole.checked_add(fraction)
+    }
+
+    /// Parses a plain decimal string (e.g. `"1250.5"`) into currency minor units.
+    fn parse_fiat_amount(raw: &str, decimals: u32) -> Option<u128> {
+        let (integer, fraction) = raw.trim().split_once('.').unwrap_or((raw.trim(), ""));
+        if integer.is_empty() || !integer.bytes().all(|ch| ch.is_ascii_digit()) {
+            return None;
+        }
+        if !fraction.bytes().all(|ch| ch.is_ascii_digit()) {
+            return None;
+        }
+
+        let significant = fraction.trim_end_matches('0');
+        if significant.len() > decimals as usize {
+            return None;
+        }
+
+        let scale = 10u128.checked_pow(decimals)?;
+        let mut minor = integer.parse::<u128>().ok()?.checked_mul(scale)?;
+        if !significant.is_empty() {
+            let padding = 10u128.pow(decimals - significant.len() as u32);
+            minor = minor.checked_add(significant.parse::<u128>().ok()? * padding)?;
+        }
+        Some(minor)
+    }
+
+    fn format_fiat_amount(minor: u128, decimals: u32) -> String {
+        if decimals == 0 {
+            return minor.to_string();
+        }
+        let scale = 10u128.pow(decimals);
+        format!(
+            "{}.{:0width$}",
+            minor / scale,
+            minor % scale,
+            width = decimals as usize
+        )
+    }
+
+    /// Returns the normalized currency code and the conversion rate to lock on
+    /// the intent. Deposits without currency rates (created before rates
+    /// existed) must set them with `update_deposit_terms` first.
+    fn resolve_intent_currency(
+        &self,
+        deposit_id: u64,
+        payment_method_index: usize,
+        currency_code: &str,
+    ) -> (String, u128) {
+        let currency_code = currency_code.trim().to_uppercase();
+        assert!(!currency_code.is_empty(), "currency_code is required");
+
+        let payment_method_data = self
+            .deposit_payment_method_data
+            .get(&deposit_id)
+            .expect("Deposit has no currency rates");
+        let min_conversion_rate = payment_method_data[payment_method_index]
+            .currencies
+            .iter()
+            .find(|currency| currency.code == currency_code)
+            .map(|currency| currency.min_conversion_rate)
+            .unwrap_or_else(|| env::panic_str("Currency not supported for payment method"));
+
+        let peg = self.deposit_price_pegs.get(&deposit_id).and_then(|pegs| {
+            pegs.into_iter()
+                .find(|peg| peg.currency_code == currency_code)
+        });
+        let conversion_rate = match peg {
+            Some(peg) => self.pegged_conversion_rate(&peg).max(min_conversion_rate),
+            None => min_conversion_rate,
+        };
+
+        (currency_code, conversion_rate)
+    }
+
+    /// Drops price pegs on currencies that `payment_method_data` no longer quotes.
+    fn retain_quoted_price_pegs(
+        &mut self,
+        deposit_id: u64,
+        payment_method_data: &[DepositPaymentMethodData],
+    ) {
+        let Some(pegs) = self.deposit_price_pegs.get(&deposit_id) else {
+            return;
+        };
+        let retained: Vec<DepositPricePeg> = pegs
+            .into_iter()
+            .filter(|peg| {
+                payment_method_data.iter().any(|data| {
+                    data.currencies
+                        .iter()
+                        .any(|currency| currency.code == peg.currency_code)
+                })
+            })
+            .collect();
+        if retained.is_empty() {
+            self.deposit_price_pegs.remove(&deposit_id);
+        } else {
+            self.deposit_price_pegs.insert(&deposit_id, &retained);
+        }
+    }
+
+    fn pegged_conversion_rate(&self, peg: &DepositPricePeg) -> u128 {
+        self.try_pegged_conversion_rate(peg)
+            .unwrap_or_else(|error| env::panic_str(error))
+    }
+
+    fn try_pegged_conversion_rate(&self, peg: &DepositPricePeg) -> Result<u128, &'static str> {
+        let feed = self
+            .price_feeds
+            .get(&peg.feed_id)
+            .ok_or("Price feed not found")?;
+        if self.now_ms().saturating_sub(feed.observed_at_ms) > self.price_max_staleness_ms {
+            return Err("Price feed is stale");
+        }
+
+        let factor = (BPS_DENOMINATOR as i64 + peg.spread_bps as i64) as u128;
+        feed.rate
+            .checked_mul(factor)
+            .map(|scaled| scaled / BPS_DENOMINATOR)
+            .ok_or("conversion rate overflow")
+    }
+
+    /// Order book entries for one open deposit: one per payment method quoting
+    /// `currency_code` at a rate `signal_intent` would currently accept.
+    fn deposit_quotes(
+        &self,
+        deposit_id: u64,
+        currency_code: &str,
+        platform: Option<&str>,
+        amount: Option<u128>,
+    ) -> Vec<OrderBookEntry> {
+        let Some(deposit) = self.deposits.get(&deposit_id) else {
+            return Vec::new();
+        };
+        let Some(payment_method_data) = self.deposit_payment_method_data.get(&deposit_id) else {
+            return Vec::new();
+        };
+        let available_amount = deposit.remaining_deposits.min(deposit.max_intent_amount);
+        if available_amount < deposit.min_intent_amount {
+            return Vec::new();
+        }
+        if let Some(amount) = amount {
+            if amount < deposit.min_intent_amount || amount > available_amount {
+                return Vec::new();
+            }
+        }
+        let peg = self.deposit_price_pegs.get(&deposit_id).and_then(|pegs| {
+            pegs.into_iter()
+                .find(|peg| peg.currency_code == currency_code)
+        });
+        let pegged_rate = match &peg {
+            Some(peg) => match self.try_pegged_conversion_rate(peg) {
+                Ok(rate) => Some(rate),
+                Err(_) => return Vec::new(),
+            },
+            None => None,
+        };
+
+        let mut entries = Vec::new();
+        for (payment_method, data) in deposit.payment_methods.iter().zip(payment_method_data) {
+            let (method_platform, _) = Self::parse_payment_method(payment_method);
+            if platform.is_some_and(|platform| platform != method_platform) {
+                continue;
+            }
+            let supported = self
+                .payment_methods
+                .get(&method_platform)
+                .is_some_and(|method| method.currencies.iter().any(|code| code == currency_code));
+            if !supported {
+                continue;
+            }
+            let Some(min_conversion_rate) = data
+                .currencies
+                .iter()
+                .find(|currency| currency.code == currency_code)
+                .map(|currency| currency.min_conversion_rate)
+            else {
+                continue;
+            };
+            let conversion_rate =
+                pegged_rate.map_or(min_conversion_rate, |rate| rate.max(min_conversion_rate));
+
+            entries.push(OrderBookEntry {
+                deposit_id,
+                depositor: deposit.depositor.clone(),
+                payment_method: payment_method.clone(),
+                platform: method_platform,
+                currency_code: currency_code.to_string(),
+                conversion_rate,
+                min_intent_amount: deposit.min_intent_amount,
+                max_intent_amount: deposit.max_intent_amount,
+                available_amount,
+                fiat_amount_due: amount
+                    .and_then(|amount| Self::fiat_amount_due(amount, conversion_rate))
+                    .map(|minor| {
+                        Self::format_fiat_amount(minor, Self::currency_decimals(currency_code))
+                    }),
+            });
+        }
+        entries
+    }
+
+    /// Quotes of `deposit_ids` in `currency_code` that `buyer` may signal on,
+    /// cheapest rate first, then largest available amount, then oldest deposit.
+    fn sorted_quotes(
+        &self,
+        deposit_ids: Vec<u64>,
+        currency_code: &str,
+        platform: Option<&str>,
+        amount: Option<u128>,
+        buyer: Option<&AccountId>,
+    ) -> Vec<OrderBookEntry> {
+        let mut entries: Vec<OrderBookEntry> = deposit_ids
+            .into_iter()
+            .filter(|deposit_id| self.buyer_may_signal(*deposit_id, buyer))
+            .flat_map(|deposit_id| self.deposit_quotes(deposit_id, currency_code, platform, amount))
+            .collect();
+        entries.sort_by(|a, b| {
+            a.conversion_rate
+                .cmp(&b.conversion_rate)
+                .then(b.available_amount.cmp(&a.available_amount))
+                .then(a.deposit_id.cmp(&b.deposit_id))
+        });
+        entries
+    }
+
+    fn currency_decimals(code: &str) -> u32 {
+        match code {
+            "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF"
+            | "UGX" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+            "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+            _ => 2,
+        }
+    }
+
+    fn normalize_payment_method_data(
+        payment_methods: &[String],
+        payment_method_data: Option<Vec<DepositPaymentMethodData>>,
+    ) -> Option<Vec<DepositPaymentMethodData>> {
+        let payment_method_data = payment_method_data?;
+        assert_eq!(
+            payment_method_data.len(),
+            payment_methods.len(),
+            "payment_method_data must match payment_methods"
+        );
+
+        Some(
+            payment_method_data
+                .into_iter()
+                .map(|data| {
+                    assert!(
+                        !data.currencies.is_empty(),
+                        "At least one currency required per payment method"
+                    );
+                    let payee_details_hash = data.payee_details_hash.trim().to_lowercase();
+                    assert!(
+                        payee_details_hash.is_empty()
+                            || decode_hex_fixed::<32>(&payee_details_hash).is_some(),
+                        "payee_details_hash must be 32-byte hex"
+                    );
+                    DepositPaymentMethodData {
+                        payee_details_hash,
+                        verification_data: data.verification_data,
+                        currencies: Self::normalize_currencies(data.currencies),
+                    }
+                })
+                .collect(),
         )
     }
 
-    fn sanitize_attestation_value(value: &str) -> String {
-        value.replace('\n', " ").trim().to_string()
+    fn normalize_currencies(currencies: Vec<Currency>) -> Vec<Currency> {
+        let mut normalized: Vec<Currency> = Vec::with_capacity(currencies.len());
+        for currency in currencies {
+            let code = currency.code.trim().to_uppercase();
+            assert!(!code.is_empty(), "currency code is required");
+            assert!(
+                currency.min_conversion_rate > 0,
+                "conversion rate must be > 0"
+            );
+            assert!(
+                normalized.iter().all(|existing| existing.code != code),
+                "duplicate currency code"
+            );
+            normalized.push(Currency {
+                code,
+                min_conversion_rate: currency.min_conversion_rate,
+            });
+        }
+        normalized
     }
 
     fn normalize_attestation_public_key_hex(value: String) -> String {
         let normalized = value.trim().to_lowercase();
         assert!(
-            Self::decode_hex_fixed::<32>(&normalized).is_some(),
+            decode_hex_fixed::<32>(&normalized).is_some(),
             "public_key_hex must be 32-byte hex"
         );
         normalized
     }
+}
 
-    fn decode_hex_fixed<const N: usize>(value: &str) -> Option<[u8; N]> {
-        let trimmed = value.trim();
-        if trimmed.len() != N * 2 {
-            return None;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::mock::MockAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn set_context(predecessor: &AccountId, attached_yocto: u128, now_ms: u64) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(predecessor.clone())
+            .attached_deposit(NearToken::from_yoctonear(attached_yocto))
+            .block_timestamp(now_ms * 1_000_000)
+            .build());
+    }
+
+    const NOW_MS: u64 = 1_700_000_000_000;
+    const VENMO: &str = "venmo::seller";
+
+    fn owner() -> AccountId {
+        accounts(0)
+    }
+
+    fn seller() -> AccountId {
+        accounts(1)
+    }
+
+    fn buyer() -> AccountId {
+        accounts(2)
+    }
+
+    fn usd_rates(min_conversion_rate: u128) -> Vec<DepositPaymentMethodData> {
+        vec![DepositPaymentMethodData {
+            payee_details_hash: String::new(),
+            verification_data: String::new(),
+            currencies: vec![Currency {
+                code: "USD".to_string(),
+                min_conversion_rate,
+            }],
+        }]
+    }
+
+    fn setup() -> Contract {
+        set_context(&owner(), 0, NOW_MS);
+        let mut contract = Contract::new(owner(), owner());
+        contract.add_payment_method(
+            "venmo".to_string(),
+            "verifier".to_string(),
+            vec!["USD".to_string(), "EUR".to_string()],
+            None,
+        );
+        contract
+    }
+
+    /// Deposit of 1000 units (intents of 10..=500) quoting USD at `rate`.
+    fn create_deposit(contract: &mut Contract, rate: u128) -> u64 {
+        set_context(&seller(), 0, NOW_MS);
+        contract.create_deposit(
+            "usdc".to_string(),
+            U128(1_000),
+            U128(10),
+            U128(500),
+            vec![VENMO.to_string()],
+            None,
+            Some(usd_rates(rate)),
+        )
+    }
+
+    fn signal(
+        contract: &mut Contract,
+        buyer: &AccountId,
+        deposit_id: u64,
+        amount: u128,
+        attached_yocto: u128,
+    ) -> String {
+        set_context(buyer, attached_yocto, NOW_MS);
+        contract.signal_intent(
+            deposit_id,
+            U128(amount),
+            VENMO.to_string(),
+            "usd".to_string(),
+            "buyer.near".to_string(),
+            "near".to_string(),
+            None,
+        )
+    }
+
+    #[test]
+    fn parses_fiat_amounts_into_minor_units() {
+        for (raw, decimals, expected) in [
+            ("1250.5", 2, Some(125_050)),
+            ("1250.50", 2, Some(125_050)),
+            (" 7 ", 2, Some(700)),
+            ("7.", 2, Some(700)),
+            ("1.500", 2, Some(150)),
+            ("1500", 0, Some(1_500)),
+            ("0.125", 3, Some(125)),
+            ("1.005", 2, None),
+            ("1.5", 0, None),
+            (".5", 2, None),
+            ("", 2, None),
+            ("-1", 2, None),
+            ("1,000", 2, None),
+            ("1.2.3", 2, None),
+            ("1e3", 2, None),
+        ] {
+            assert_eq!(
+                Contract::parse_fiat_amount(raw, decimals),
+                expected,
+                "{raw}"
+            );
         }
+    }
 
-        let bytes = trimmed.as_bytes();
-        let mut out = [0u8; N];
-        let mut index = 0usize;
-        while index < N {
-            let high = Self::decode_hex_nibble(bytes[index * 2])?;
-            let low = Self::decode_hex_nibble(bytes[index * 2 + 1])?;
-            out[index] = (high << 4) | low;
-            index += 1;
+    #[test]
+    fn fiat_amount_due_rounds_up() {
+        // 1 token (18 decimals) at 1.00 USD per token.
+        assert_eq!(Contract::fiat_amount_due(PRECISE_UNIT, 100), Some(100));
+        assert_eq!(Contract::fiat_amount_due(PRECISE_UNIT / 2, 101), Some(51));
+        assert_eq!(Contract::fiat_amount_due(1, 1), Some(1));
+        assert_eq!(Contract::fiat_amount_due(0, 100), Some(0));
+        assert_eq!(Contract::fiat_amount_due(u128::MAX, u128::MAX), None);
+    }
+
+    #[test]
+    fn tolerance_lowers_min_attested_amount_rounding_up() {
+        assert_eq!(Contract::min_attested_amount(10_000, 0), 10_000);
+        assert_eq!(Contract::min_attested_amount(10_000, 100), 9_900);
+        assert_eq!(
+            Contract::min_attested_amount(10_000, MAX_ATTESTATION_AMOUNT_TOLERANCE_BPS),
+            9_000
+        );
+        // 101 * 0.99 = 99.99, which rounds up to 100.
+        assert_eq!(Contract::min_attested_amount(101, 100), 100);
+        assert_eq!(Contract::min_attested_amount(1, 100), 1);
+        assert_eq!(Contract::min_attested_amount(0, 100), 0);
+    }
+
+    fn legacy_intent(intent_hash: &str) -> Intent {
+        Intent {
+            intent_hash: intent_hash.to_string(),
+            buyer: accounts(2),
+            deposit_id: 1,
+            amount: PRECISE_UNIT,
+            timestamp: 0,
+            payment_method: "venmo::seller".to_string(),
+            currency_code: "USD".to_string(),
+            status: IntentStatus::Signaled,
+            recipient: "recipient".to_string(),
+            chain: "near".to_string(),
         }
-        Some(out)
     }
 
-    fn decode_hex_nibble(ch: u8) -> Option<u8> {
-        match ch {
-            b'0'..=b'9' => Some(ch - b'0'),
-            b'a'..=b'f' => Some(ch - b'a' + 10),
-            b'A'..=b'F' => Some(ch - b'A' + 10),
-            _ => None,
+    #[test]
+    fn attested_amount_is_checked_against_locked_rate() {
+        set_context(&accounts(0), 0, 1);
+        let mut contract = Contract::new(accounts(0), accounts(0));
+        let intent = legacy_intent("intent:1");
+        contract
+            .intent_conversion_rates
+            .insert(&intent.intent_hash, &100);
+
+        contract.assert_attested_amount_covers_intent(&intent, "1.00");
+        contract.attestation_amount_tolerance_bps = 100;
+        contract.assert_attested_amount_covers_intent(&intent, "0.99");
+    }
+
+    #[test]
+    #[should_panic(expected = "attestation amount below intent amount")]
+    fn attested_amount_below_locked_rate_is_rejected() {
+        set_context(&accounts(0), 0, 1);
+        let mut contract = Contract::new(accounts(0), accounts(0));
+        let intent = legacy_intent("intent:1");
+        contract
+            .intent_conversion_rates
+            .insert(&intent.intent_hash, &100);
+
+        contract.assert_attested_amount_covers_intent(&intent, "0.99");
+    }
+
+    #[test]
+    fn intent_without_locked_rate_keeps_legacy_amount_check() {
+        set_context(&accounts(0), 0, 1);
+        let contract = Contract::new(accounts(0), accounts(0));
+
+        contract.assert_attested_amount_covers_intent(&legacy_intent("intent:1"), "0.01");
+    }
+
+    #[test]
+    fn server_names_are_only_enforced_once_configured() {
+        set_context(&accounts(0), 0, 1);
+        let mut contract = Contract::new(accounts(0), accounts(0));
+        contract.add_payment_method(
+            "Venmo".to_string(),
+            "v".to_string(),
+            vec!["usd".to_string()],
+            None,
+        );
+
+        contract.assert_attested_server_name_allowed("venmo", "api.anything.example");
+
+        contract.set_payment_method_server_names(
+            "venmo".to_string(),
+            vec!["Account.Venmo.com.".to_string()],
+        );
+        contract.assert_attested_server_name_allowed("venmo", "account.venmo.com");
+        contract.assert_attested_server_name_allowed("venmo", "ACCOUNT.venmo.com.");
+    }
+
+    #[test]
+    #[should_panic(expected = "attestation server name is not allowed for payment method")]
+    fn unlisted_server_name_is_rejected() {
+        set_context(&accounts(0), 0, 1);
+        let mut contract = Contract::new(accounts(0), accounts(0));
+        contract.add_payment_method(
+            "venmo".to_string(),
+            "v".to_string(),
+            vec!["USD".to_string()],
+            Some(vec!["account.venmo.com".to_string()]),
+        );
+
+        contract.assert_attested_server_name_allowed("venmo", "evil.example");
+    }
+
+    const ATTESTATION_PUBLIC_KEY_HEX: &str =
+        "ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c";
+
+    /// Unsigned v2 attestation matching the intent's terms, issued a second
+    /// after it was signaled.
+    fn attestation_for(contract: &Contract, intent_hash: &str) -> AttestationRecord {
+        let intent = contract.get_intent(intent_hash.to_string()).unwrap();
+        let (platform, tagname) = Contract::parse_payment_method(&intent.payment_method);
+        let issued_at_ms = intent.timestamp / 1_000_000 + 1_000;
+        AttestationRecord {
+            attestation_id: format!("attestation:{}", intent_hash),
+            version: ATTESTATION_VERSION_V2.to_string(),
+            session_id: format!("session:{}", intent_hash),
+            intent_id: Some(intent_hash.to_string()),
+            server_name: "account.venmo.com".to_string(),
+            expected_memo: Some(Contract::build_intent_transfer_memo(
+                intent_hash,
+                intent.deposit_id,
+            )),
+            expected_amount: Some("1000000.00".to_string()),
+            expected_currency: Some(intent.currency_code.clone()),
+            expected_platform: Some(platform),
+            expected_tagname: Some(tagname),
+            transcript_digest_sha256: "ab".repeat(32),
+            issued_at_ms,
+            expires_at_ms: issued_at_ms + 600_000,
+            payment_at_ms: Some(issued_at_ms - 500),
+            checks: anypay_attestation_types::AttestationChecks {
+                recv_body_revealed: true,
+                memo_match: Some(true),
+                amount_match: Some(true),
+                currency_match: Some(true),
+                platform_match: Some(true),
+                tagname_match: Some(true),
+                policy_passed: true,
+            },
+            signature: anypay_attestation_types::AttestationSignature {
+                algorithm: "ed25519".to_string(),
+                public_key_hex: ATTESTATION_PUBLIC_KEY_HEX.to_string(),
+                signature_hex: String::new(),
+            },
         }
     }
+
+    fn fulfill_with_attestation(
+        contract: &mut Contract,
+        intent_hash: &str,
+        attestation: &AttestationRecord,
+        now_ms: u64,
+    ) -> PromiseOrValue<()> {
+        set_context(&accounts(4), 0, now_ms);
+        contract.fulfill_intent_with_attestation(
+            intent_hash.to_string(),
+            near_sdk::serde_json::to_string(attestation).unwrap(),
+            None,
+        )
+    }
+
+    fn attested_intent() -> (Contract, String, AttestationRecord) {
+        let mut contract = setup();
+        contract.set_attestation_public_key_hex(ATTESTATION_PUBLIC_KEY_HEX.to_string());
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+        let attestation = attestation_for(&contract, &intent_hash);
+        (contract, intent_hash, attestation)
+    }
+
+    #[test]
+    #[should_panic(expected = "attestation was issued before the intent was signaled")]
+    fn attestation_issued_before_the_intent_is_rejected() {
+        let (mut contract, intent_hash, mut attestation) = attested_intent();
+        attestation.issued_at_ms = NOW_MS - 1;
+        attestation.payment_at_ms = None;
+
+        fulfill_with_attestation(&mut contract, &intent_hash, &attestation, NOW_MS + 2_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "attestation has expired")]
+    fn expired_attestation_is_rejected() {
+        let (mut contract, intent_hash, attestation) = attested_intent();
+
+        fulfill_with_attestation(
+            &mut contract,
+            &intent_hash,
+            &attestation,
+            attestation.expires_at_ms + 1,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "attested payment was made before the intent was signaled")]
+    fn attested_payment_before_the_intent_is_rejected() {
+        let (mut contract, intent_hash, mut attestation) = attested_intent();
+        attestation.payment_at_ms = Some(NOW_MS - 1);
+
+        fulfill_with_attestation(&mut contract, &intent_hash, &attestation, NOW_MS + 2_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "attested payment was made after the attestation was issued")]
+    fn attested_payment_after_issuance_is_rejected() {
+        let (mut contract, intent_hash, mut attestation) = attested_intent();
+        attestation.payment_at_ms = Some(attestation.issued_at_ms + 1);
+
+        fulfill_with_attestation(&mut contract, &intent_hash, &attestation, NOW_MS + 2_000);
+    }
+
+    fn add_payment_method(contract: &mut Contract, name: &str, currencies: &[&str]) {
+        set_context(&owner(), 0, NOW_MS);
+        contract.add_payment_method(
+            name.to_string(),
+            "verifier".to_string(),
+            currencies.iter().map(|code| code.to_string()).collect(),
+            None,
+        );
+    }
+
+    #[test]
+    fn payment_methods_page_in_registration_order() {
+        let mut contract = setup();
+        add_payment_method(&mut contract, "Wise", &["eur", "EUR", "gbp"]);
+        add_payment_method(&mut contract, "revolut", &["EUR"]);
+        add_payment_method(&mut contract, "zelle", &["USD"]);
+
+        let names = |methods: Vec<PaymentMethod>| -> Vec<String> {
+            methods.into_iter().map(|method| method.name).collect()
+        };
+        assert_eq!(
+            names(contract.get_payment_methods(None, None)),
+            vec!["venmo", "wise", "revolut", "zelle"]
+        );
+        assert_eq!(
+            names(contract.get_payment_methods(Some(1), Some(2))),
+            vec!["wise", "revolut"]
+        );
+        assert_eq!(
+            names(contract.get_payment_methods(Some(3), Some(2))),
+            vec!["zelle"]
+        );
+        assert!(contract.get_payment_methods(Some(4), None).is_empty());
+        assert_eq!(
+            contract
+                .get_payment_method("WISE".to_string())
+                .unwrap()
+                .currencies,
+            vec!["EUR", "GBP"]
+        );
+
+        contract.remove_payment_method("wise".to_string());
+        assert_eq!(
+            names(contract.get_payment_methods(None, None)),
+            vec!["venmo", "zelle", "revolut"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Payment method not registered")]
+    fn deposit_with_unregistered_payment_method_is_rejected() {
+        let mut contract = setup();
+        set_context(&seller(), 0, NOW_MS);
+        contract.create_deposit(
+            "usdc".to_string(),
+            U128(1_000),
+            U128(10),
+            U128(500),
+            vec!["paypal::seller".to_string()],
+            None,
+            Some(usd_rates(100)),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Currency not supported by payment method")]
+    fn deposit_with_unsupported_currency_is_rejected() {
+        let mut contract = setup();
+        add_payment_method(&mut contract, "venmo", &["EUR"]);
+        create_deposit(&mut contract, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Payment method not registered")]
+    fn signal_on_removed_payment_method_is_rejected() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_context(&owner(), 0, NOW_MS);
+        contract.remove_payment_method("venmo".to_string());
+
+        signal(&mut contract, &buyer(), deposit_id, 50, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Currency not supported by payment method")]
+    fn signal_in_currency_dropped_by_payment_method_is_rejected() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        add_payment_method(&mut contract, "venmo", &["EUR"]);
+
+        signal(&mut contract, &buyer(), deposit_id, 50, 0);
+    }
+
+    const BUYER_ENCRYPTION_PUBLIC_KEY: &str =
+        "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a";
+
+    /// Deposit whose Venmo handle is only published as a salted hash.
+    fn create_hashed_payee_deposit(contract: &mut Contract) -> u64 {
+        set_context(&seller(), 0, NOW_MS);
+        let mut payment_method_data = usd_rates(100);
+        payment_method_data[0].payee_details_hash =
+            Contract::compute_payee_details_hash("venmo", "seller", "salt");
+        contract.create_deposit(
+            "usdc".to_string(),
+            U128(1_000),
+            U128(10),
+            U128(500),
+            vec!["venmo".to_string()],
+            None,
+            Some(payment_method_data),
+        )
+    }
+
+    fn signal_hashed_payee(
+        contract: &mut Contract,
+        deposit_id: u64,
+        buyer_encryption_public_key: Option<&str>,
+    ) -> String {
+        set_context(&buyer(), 0, NOW_MS);
+        contract.signal_intent(
+            deposit_id,
+            U128(50),
+            "venmo".to_string(),
+            "usd".to_string(),
+            "buyer.near".to_string(),
+            "near".to_string(),
+            buyer_encryption_public_key.map(str::to_string),
+        )
+    }
+
+    #[test]
+    fn depositor_and_delegate_reveal_hashed_payee_details() {
+        let mut contract = setup();
+        let deposit_id = create_hashed_payee_deposit(&mut contract);
+        contract.set_delegate(deposit_id, accounts(5));
+        let intent_hash =
+            signal_hashed_payee(&mut contract, deposit_id, Some(BUYER_ENCRYPTION_PUBLIC_KEY));
+        let reveal = contract
+            .get_intent_payee_reveal(intent_hash.clone())
+            .unwrap();
+        assert_eq!(
+            reveal.buyer_encryption_public_key,
+            BUYER_ENCRYPTION_PUBLIC_KEY
+        );
+        assert_eq!(reveal.encrypted_payee_details, None);
+
+        set_context(&seller(), 0, NOW_MS + 1_000);
+        contract.reveal_payee_details(intent_hash.clone(), "sealed:seller".to_string());
+        set_context(&accounts(5), 0, NOW_MS + 2_000);
+        contract.reveal_payee_details(intent_hash.clone(), " sealed:delegate ".to_string());
+
+        let reveal = contract.get_intent_payee_reveal(intent_hash).unwrap();
+        assert_eq!(
+            reveal.encrypted_payee_details.as_deref(),
+            Some("sealed:delegate")
+        );
+        assert_eq!(reveal.revealed_at_ms, NOW_MS + 2_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "buyer_encryption_public_key must be 32-byte hex")]
+    fn hashed_payee_requires_buyer_encryption_key() {
+        let mut contract = setup();
+        let deposit_id = create_hashed_payee_deposit(&mut contract);
+
+        signal_hashed_payee(&mut contract, deposit_id, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn only_deposit_manager_reveals_payee_details() {
+        let mut contract = setup();
+        let deposit_id = create_hashed_payee_deposit(&mut contract);
+        let intent_hash =
+            signal_hashed_payee(&mut contract, deposit_id, Some(BUYER_ENCRYPTION_PUBLIC_KEY));
+
+        set_context(&buyer(), 0, NOW_MS);
+        contract.reveal_payee_details(intent_hash, "sealed:buyer".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Intent does not use hashed payee details")]
+    fn plain_payee_intents_have_nothing_to_reveal() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+
+        set_context(&seller(), 0, NOW_MS);
+        contract.reveal_payee_details(intent_hash, "sealed:seller".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "attestation payee details hash mismatch")]
+    fn attestation_for_another_payee_is_rejected() {
+        let mut contract = setup();
+        contract.set_attestation_public_key_hex(ATTESTATION_PUBLIC_KEY_HEX.to_string());
+        let deposit_id = create_hashed_payee_deposit(&mut contract);
+        let intent_hash =
+            signal_hashed_payee(&mut contract, deposit_id, Some(BUYER_ENCRYPTION_PUBLIC_KEY));
+        let mut attestation = attestation_for(&contract, &intent_hash);
+        attestation.expected_tagname = Some("mallory".to_string());
+
+        set_context(&accounts(4), 0, NOW_MS + 2_000);
+        contract.fulfill_intent_with_attestation(
+            intent_hash,
+            near_sdk::serde_json::to_string(&attestation).unwrap(),
+            Some("salt".to_string()),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "payee_details_salt is required")]
+    fn hashed_payee_attestation_requires_the_salt() {
+        let mut contract = setup();
+        contract.set_attestation_public_key_hex(ATTESTATION_PUBLIC_KEY_HEX.to_string());
+        let deposit_id = create_hashed_payee_deposit(&mut contract);
+        let intent_hash =
+            signal_hashed_payee(&mut contract, deposit_id, Some(BUYER_ENCRYPTION_PUBLIC_KEY));
+        let mut attestation = attestation_for(&contract, &intent_hash);
+        attestation.expected_tagname = Some("seller".to_string());
+
+        fulfill_with_attestation(&mut contract, &intent_hash, &attestation, NOW_MS + 2_000);
+    }
+
+    /// The attestation backend's development signing key, derived the way
+    /// `load_signing_key_from_env` does without `ATTESTATION_SIGNING_SECRET_HEX`.
+    fn backend_signing_key() -> ed25519_dalek::SigningKey {
+        let seed: [u8; 32] = env::sha256(b"anypay-attestation-backend-dev-key")
+            .try_into()
+            .unwrap();
+        ed25519_dalek::SigningKey::from_bytes(&seed)
+    }
+
+    /// Signs `attestation` as the backend's `create_from_webhook` does.
+    fn backend_sign(attestation: &mut AttestationRecord) -> String {
+        let key = backend_signing_key();
+        let public_key_hex = hex::encode(key.verifying_key().as_bytes());
+        attestation.signature.public_key_hex = public_key_hex.clone();
+        let message = anypay_attestation_types::canonical_message(attestation).unwrap();
+        attestation.signature.signature_hex =
+            hex::encode(ed25519_dalek::Signer::sign(&key, &message).to_bytes());
+        public_key_hex
+    }
+
+    #[test]
+    fn backend_signed_attestation_fulfills_intent() {
+        let (mut contract, intent_hash, mut attestation) = attested_intent();
+        // The backend does not attest payment times yet.
+        attestation.payment_at_ms = None;
+        let public_key_hex = backend_sign(&mut attestation);
+        set_context(&owner(), 0, NOW_MS);
+        contract.set_attestation_public_key_hex(public_key_hex);
+
+        fulfill_with_attestation(&mut contract, &intent_hash, &attestation, NOW_MS + 2_000);
+
+        assert_eq!(
+            contract.get_intent(intent_hash.clone()).unwrap().status,
+            IntentStatus::Fulfilled
+        );
+        assert!(contract
+            .used_attestation_sessions
+            .get(&attestation.session_id)
+            .is_some());
+        assert!(contract.get_intent_attestation(intent_hash).is_some());
+        assert_eq!(contract.get_account_stats(buyer()).buyer.fulfilled, 1);
+    }
+
+    #[test]
+    fn backend_signed_attestation_matches_hashed_payee() {
+        let mut contract = setup();
+        let deposit_id = create_hashed_payee_deposit(&mut contract);
+        let intent_hash =
+            signal_hashed_payee(&mut contract, deposit_id, Some(BUYER_ENCRYPTION_PUBLIC_KEY));
+        let mut attestation = attestation_for(&contract, &intent_hash);
+        attestation.expected_tagname = Some("seller".to_string());
+        let public_key_hex = backend_sign(&mut attestation);
+        set_context(&owner(), 0, NOW_MS);
+        contract.set_attestation_public_key_hex(public_key_hex);
+
+        set_context(&accounts(4), 0, NOW_MS + 2_000);
+        contract.fulfill_intent_with_attestation(
+            intent_hash.clone(),
+            near_sdk::serde_json::to_string(&attestation).unwrap(),
+            Some("salt".to_string()),
+        );
+
+        assert_eq!(
+            contract.get_intent(intent_hash).unwrap().status,
+            IntentStatus::Fulfilled
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "attestation signature verification failed")]
+    fn backend_attestation_altered_after_signing_is_rejected() {
+        let (mut contract, intent_hash, mut attestation) = attested_intent();
+        let public_key_hex = backend_sign(&mut attestation);
+        set_context(&owner(), 0, NOW_MS);
+        contract.set_attestation_public_key_hex(public_key_hex);
+        attestation.expected_amount = Some("2000000.00".to_string());
+
+        fulfill_with_attestation(&mut contract, &intent_hash, &attestation, NOW_MS + 2_000);
+    }
+
+    #[test]
+    fn signal_locks_deposit_conversion_rate() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+
+        let intent = contract.get_intent(intent_hash.clone()).unwrap();
+        assert_eq!(intent.currency_code, "USD");
+        assert_eq!(
+            contract.intent_conversion_rates.get(&intent_hash),
+            Some(100)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Deposit has no currency rates")]
+    fn signal_requires_deposit_currency_rates() {
+        let mut contract = setup();
+        set_context(&seller(), 0, NOW_MS);
+        let deposit_id = contract.create_deposit(
+            "usdc".to_string(),
+            U128(1_000),
+            U128(10),
+            U128(500),
+            vec![VENMO.to_string()],
+            None,
+            None,
+        );
+
+        signal(&mut contract, &buyer(), deposit_id, 50, 0);
+    }
+
+    fn peg(currency_code: &str) -> DepositPricePeg {
+        DepositPricePeg {
+            currency_code: currency_code.to_string(),
+            feed_id: format!("{}-feed", currency_code.to_lowercase()),
+            spread_bps: 0,
+        }
+    }
+
+    #[test]
+    fn update_deposit_terms_drops_pegs_on_removed_currencies() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_context(&seller(), 0, NOW_MS);
+        contract.update_deposit_terms(
+            deposit_id,
+            None,
+            None,
+            Some(vec![vec![
+                Currency {
+                    code: "USD".to_string(),
+                    min_conversion_rate: 100,
+                },
+                Currency {
+                    code: "EUR".to_string(),
+                    min_conversion_rate: 90,
+                },
+            ]]),
+        );
+        contract.set_deposit_price_pegs(deposit_id, vec![peg("USD"), peg("EUR")]);
+
+        contract.update_deposit_terms(
+            deposit_id,
+            None,
+            None,
+            Some(vec![vec![Currency {
+                code: "USD".to_string(),
+                min_conversion_rate: 110,
+            }]]),
+        );
+        let pegs = contract.get_deposit_price_pegs(deposit_id);
+        assert_eq!(pegs.len(), 1);
+        assert_eq!(pegs[0].currency_code, "USD");
+
+        contract.update_deposit_terms(
+            deposit_id,
+            None,
+            None,
+            Some(vec![vec![Currency {
+                code: "EUR".to_string(),
+                min_conversion_rate: 90,
+            }]]),
+        );
+        assert!(contract.get_deposit_price_pegs(deposit_id).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Min intent amount exceeds remaining deposits")]
+    fn update_deposit_terms_checks_min_on_empty_deposit() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_context(&seller(), 0, NOW_MS);
+        contract.withdraw_deposit(deposit_id);
+
+        contract.update_deposit_terms(deposit_id, Some(U128(20)), None, None);
+    }
+
+    fn oracle() -> AccountId {
+        accounts(3)
+    }
+
+    fn push_price(contract: &mut Contract, feed_id: &str, rate: u128, observed_at_ms: u64) {
+        set_context(&oracle(), 0, NOW_MS);
+        contract.oracle_push_price(feed_id.to_string(), U128(rate), observed_at_ms);
+    }
+
+    #[test]
+    fn spread_bps_scales_feed_rate() {
+        let mut contract = setup();
+        contract.set_oracle_account_id(oracle());
+        push_price(&mut contract, "usd-feed", 10_000, NOW_MS - 1);
+
+        for (spread_bps, expected) in [(0, 10_000), (-100, 9_900), (250, 10_250), (-5_000, 5_000)] {
+            let peg = DepositPricePeg {
+                spread_bps,
+                ..peg("USD")
+            };
+            assert_eq!(contract.try_pegged_conversion_rate(&peg), Ok(expected));
+        }
+
+        // Fractions of a unit round down.
+        push_price(&mut contract, "usd-feed", 333, NOW_MS);
+        let peg = DepositPricePeg {
+            spread_bps: -1,
+            ..peg("USD")
+        };
+        assert_eq!(contract.try_pegged_conversion_rate(&peg), Ok(332));
+    }
+
+    #[test]
+    fn stale_or_missing_feed_is_rejected() {
+        let mut contract = setup();
+        contract.set_oracle_account_id(oracle());
+        let observed_at_ms = NOW_MS - DEFAULT_PRICE_MAX_STALENESS_MS;
+        push_price(&mut contract, "usd-feed", 10_000, observed_at_ms);
+
+        assert_eq!(contract.try_pegged_conversion_rate(&peg("USD")), Ok(10_000));
+        set_context(&buyer(), 0, NOW_MS + 1);
+        assert_eq!(
+            contract.try_pegged_conversion_rate(&peg("USD")),
+            Err("Price feed is stale")
+        );
+        assert_eq!(
+            contract.try_pegged_conversion_rate(&peg("EUR")),
+            Err("Price feed not found")
+        );
+    }
+
+    #[test]
+    fn pegged_rate_is_floored_at_min_conversion_rate() {
+        let mut contract = setup();
+        contract.set_oracle_account_id(oracle());
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_context(&seller(), 0, NOW_MS);
+        contract.set_deposit_price_pegs(deposit_id, vec![peg("USD")]);
+
+        push_price(&mut contract, "usd-feed", 120, NOW_MS - 10);
+        assert_eq!(
+            contract.resolve_intent_currency(deposit_id, 0, "usd").1,
+            120
+        );
+        push_price(&mut contract, "usd-feed", 80, NOW_MS);
+        assert_eq!(
+            contract.resolve_intent_currency(deposit_id, 0, "usd").1,
+            100
+        );
+    }
+
+    #[test]
+    fn oracle_keeps_latest_observation() {
+        let mut contract = setup();
+        contract.set_oracle_account_id(oracle());
+        push_price(&mut contract, "usd-feed", 120, NOW_MS - 10);
+        push_price(&mut contract, "usd-feed", 110, NOW_MS - 20);
+
+        let feed = contract.get_price_feed("usd-feed".to_string()).unwrap();
+        assert_eq!(feed.rate, 120);
+        assert_eq!(feed.observed_at_ms, NOW_MS - 10);
+        assert_eq!(feed.updated_at_ms, NOW_MS);
+    }
+
+    #[test]
+    #[should_panic(expected = "Oracle only")]
+    fn only_oracle_pushes_prices() {
+        let mut contract = setup();
+        contract.set_oracle_account_id(oracle());
+
+        set_context(&seller(), 0, NOW_MS);
+        contract.oracle_push_price("usd-feed".to_string(), U128(120), NOW_MS);
+    }
+
+    #[test]
+    #[should_panic(expected = "observed_at_ms is in the future")]
+    fn oracle_rejects_future_observations() {
+        let mut contract = setup();
+        contract.set_oracle_account_id(oracle());
+
+        push_price(&mut contract, "usd-feed", 120, NOW_MS + 1);
+    }
+
+    const ONE_NEAR: u128 = 1_000_000_000_000_000_000_000_000;
+
+    /// NEAR transfers scheduled since the last `set_context`.
+    fn transfers() -> Vec<(AccountId, u128)> {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| {
+                let receiver_id = receipt.receiver_id;
+                receipt
+                    .actions
+                    .into_iter()
+                    .filter_map(move |action| match action {
+                        MockAction::Transfer { deposit, .. } => {
+                            Some((receiver_id.clone(), deposit.as_yoctonear()))
+                        }
+                        _ => None,
+                    })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn storage_deposit_is_not_held_as_collateral() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+
+        let intent_hash = signal(
+            &mut contract,
+            &buyer(),
+            deposit_id,
+            50,
+            INTENT_STORAGE_DEPOSIT_YOCTO,
+        );
+        assert_eq!(contract.get_intent_collateral(intent_hash.clone()).0, 0);
+
+        contract.cancel_intent(intent_hash);
+        assert!(transfers().is_empty());
+    }
+
+    #[test]
+    fn cancel_refunds_collateral_to_buyer() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+
+        let intent_hash = signal(
+            &mut contract,
+            &buyer(),
+            deposit_id,
+            50,
+            INTENT_STORAGE_DEPOSIT_YOCTO + ONE_NEAR,
+        );
+        assert_eq!(
+            contract.get_intent_collateral(intent_hash.clone()).0,
+            ONE_NEAR
+        );
+
+        set_context(&buyer(), 0, NOW_MS);
+        contract.cancel_intent(intent_hash.clone());
+        assert_eq!(transfers(), vec![(buyer(), ONE_NEAR)]);
+        assert_eq!(contract.get_intent_collateral(intent_hash).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attached deposit is below intent collateral")]
+    fn min_collateral_excludes_storage_deposit() {
+        let mut contract = setup();
+        contract.set_min_intent_collateral_yocto(U128(ONE_NEAR));
+        let deposit_id = create_deposit(&mut contract, 100);
+
+        signal(&mut contract, &buyer(), deposit_id, 50, ONE_NEAR);
+    }
+
+    #[test]
+    fn expire_returns_liquidity_and_slashes_collateral() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(
+            &mut contract,
+            &buyer(),
+            deposit_id,
+            50,
+            INTENT_STORAGE_DEPOSIT_YOCTO + ONE_NEAR,
+        );
+        assert_eq!(contract.get_buyer_open_intent_count(buyer()), 1);
+
+        set_context(
+            &accounts(4),
+            0,
+            NOW_MS + contract.intent_expiration_period / 1_000_000,
+        );
+        contract.expire_intent(intent_hash.clone());
+
+        assert_eq!(transfers(), vec![(seller(), ONE_NEAR)]);
+        let intent = contract.get_intent(intent_hash).unwrap();
+        assert_eq!(intent.status, IntentStatus::Expired);
+        let deposit = contract.get_deposit(deposit_id).unwrap();
+        assert_eq!(deposit.remaining_deposits, 1_000);
+        assert_eq!(deposit.outstanding_intents, 0);
+        assert_eq!(contract.get_buyer_open_intent_count(buyer()), 0);
+        assert!(contract.get_deposit_active_intents(deposit_id).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Intent has not expired")]
+    fn expire_waits_for_expiration_period() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+
+        set_context(
+            &accounts(4),
+            0,
+            NOW_MS + contract.intent_expiration_period / 1_000_000 - 1,
+        );
+        contract.expire_intent(intent_hash);
+    }
+
+    #[test]
+    fn buyer_limits_are_off_by_default() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+
+        for _ in 0..6 {
+            let intent_hash = signal(&mut contract, &buyer(), deposit_id, 10, 0);
+            contract.cancel_intent(intent_hash);
+        }
+        for _ in 0..6 {
+            signal(&mut contract, &buyer(), deposit_id, 10, 0);
+        }
+        assert_eq!(contract.get_buyer_open_intent_count(buyer()), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max intents per buyer reached")]
+    fn max_intents_per_buyer_caps_open_intents() {
+        let mut contract = setup();
+        contract.set_max_intents_per_buyer(2);
+        let deposit_id = create_deposit(&mut contract, 100);
+
+        let first = signal(&mut contract, &buyer(), deposit_id, 10, 0);
+        signal(&mut contract, &buyer(), deposit_id, 10, 0);
+        contract.cancel_intent(first);
+        signal(&mut contract, &buyer(), deposit_id, 10, 0);
+        assert_eq!(contract.get_buyer_open_intent_count(buyer()), 2);
+        signal(&mut contract, &accounts(4), deposit_id, 10, 0);
+
+        signal(&mut contract, &buyer(), deposit_id, 10, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Deposit is in cancel cooldown for buyer")]
+    fn cancel_cooldown_blocks_the_same_deposit() {
+        let mut contract = setup();
+        contract.set_intent_cancel_cooldown_ms(600_000);
+        let deposit_id = create_deposit(&mut contract, 100);
+        let other_deposit_id = create_deposit(&mut contract, 100);
+
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 10, 0);
+        contract.cancel_intent(intent_hash);
+        assert_eq!(
+            contract.get_buyer_deposit_cooldown_until_ms(buyer(), deposit_id),
+            Some(NOW_MS + 600_000)
+        );
+        signal(&mut contract, &buyer(), other_deposit_id, 10, 0);
+
+        signal(&mut contract, &buyer(), deposit_id, 10, 0);
+    }
+
+    #[test]
+    fn cancel_cooldown_elapses() {
+        let mut contract = setup();
+        contract.set_intent_cancel_cooldown_ms(600_000);
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 10, 0);
+        contract.cancel_intent(intent_hash);
+
+        set_context(&buyer(), 0, NOW_MS + 600_000);
+        assert_eq!(
+            contract.get_buyer_deposit_cooldown_until_ms(buyer(), deposit_id),
+            None
+        );
+        contract.signal_intent(
+            deposit_id,
+            U128(10),
+            VENMO.to_string(),
+            "USD".to_string(),
+            "buyer.near".to_string(),
+            "near".to_string(),
+            None,
+        );
+        assert_eq!(contract.get_buyer_open_intent_count(buyer()), 1);
+    }
+
+    fn set_buyer_policy(contract: &mut Contract, deposit_id: u64, policy: BuyerPolicy) {
+        set_context(&seller(), 0, NOW_MS);
+        contract.set_deposit_buyer_policy(deposit_id, policy);
+        contract.set_deposit_buyer_allowlist(deposit_id, vec![accounts(4)]);
+    }
+
+    #[test]
+    fn allowlist_policy_admits_listed_buyers() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_buyer_policy(&mut contract, deposit_id, BuyerPolicy::Allowlist);
+
+        let intent_hash = signal(&mut contract, &accounts(4), deposit_id, 50, 0);
+        assert_eq!(
+            contract.get_intent(intent_hash).unwrap().status,
+            IntentStatus::Signaled
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Buyer is not allowlisted for deposit")]
+    fn allowlist_policy_rejects_unlisted_buyers() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_buyer_policy(&mut contract, deposit_id, BuyerPolicy::Allowlist);
+
+        signal(&mut contract, &buyer(), deposit_id, 50, 0);
+    }
+
+    #[test]
+    fn manual_approval_locks_liquidity_on_approval() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_buyer_policy(&mut contract, deposit_id, BuyerPolicy::ManualApproval);
+
+        let listed = signal(&mut contract, &accounts(4), deposit_id, 10, 0);
+        assert_eq!(
+            contract.get_intent(listed).unwrap().status,
+            IntentStatus::Signaled
+        );
+
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+        signal(&mut contract, &buyer(), deposit_id, 20, 0);
+        assert_eq!(
+            contract.get_intent(intent_hash.clone()).unwrap().status,
+            IntentStatus::PendingApproval
+        );
+        assert_eq!(
+            contract.get_deposit(deposit_id).unwrap().remaining_deposits,
+            990
+        );
+        assert_eq!(contract.get_buyer_open_intent_count(buyer()), 2);
+        assert_eq!(contract.get_deposit_active_intents(deposit_id).len(), 1);
+
+        set_context(&seller(), 0, NOW_MS + 60_000);
+        contract.approve_intent(intent_hash.clone());
+
+        let intent = contract.get_intent(intent_hash).unwrap();
+        assert_eq!(intent.status, IntentStatus::Signaled);
+        assert_eq!(intent.timestamp, NOW_MS * 1_000_000);
+        let deposit = contract.get_deposit(deposit_id).unwrap();
+        assert_eq!(deposit.remaining_deposits, 940);
+        assert_eq!(deposit.outstanding_intents, 60);
+        assert_eq!(contract.get_buyer_open_intent_count(buyer()), 2);
+        assert_eq!(contract.get_deposit_active_intents(deposit_id).len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max intents per buyer reached")]
+    fn pending_intents_count_against_max_intents_per_buyer() {
+        let mut contract = setup();
+        contract.set_max_intents_per_buyer(1);
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_buyer_policy(&mut contract, deposit_id, BuyerPolicy::ManualApproval);
+        signal(&mut contract, &buyer(), deposit_id, 50, 0);
+
+        signal(&mut contract, &buyer(), deposit_id, 20, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max intents reached")]
+    fn pending_intents_take_a_deposit_slot_only_on_approval() {
+        let mut contract = setup();
+        contract.set_max_intents_per_deposit(1);
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_buyer_policy(&mut contract, deposit_id, BuyerPolicy::ManualApproval);
+        let first = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+        let second = signal(&mut contract, &buyer(), deposit_id, 20, 0);
+        assert!(contract.get_deposit_active_intents(deposit_id).is_empty());
+
+        set_context(&seller(), 0, NOW_MS);
+        contract.approve_intent(first);
+        contract.approve_intent(second);
+    }
+
+    #[test]
+    fn reject_refunds_collateral_and_frees_the_slot() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_buyer_policy(&mut contract, deposit_id, BuyerPolicy::ManualApproval);
+        let intent_hash = signal(
+            &mut contract,
+            &buyer(),
+            deposit_id,
+            50,
+            INTENT_STORAGE_DEPOSIT_YOCTO + ONE_NEAR,
+        );
+
+        set_context(&seller(), 0, NOW_MS);
+        contract.reject_intent(intent_hash.clone());
+
+        assert_eq!(transfers(), vec![(buyer(), ONE_NEAR)]);
+        assert_eq!(
+            contract.get_intent(intent_hash).unwrap().status,
+            IntentStatus::Rejected
+        );
+        assert_eq!(
+            contract.get_deposit(deposit_id).unwrap().remaining_deposits,
+            1_000
+        );
+        assert!(contract.get_deposit_active_intents(deposit_id).is_empty());
+        assert_eq!(contract.get_buyer_open_intent_count(buyer()), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Deposit is in cancel cooldown for buyer")]
+    fn reject_starts_the_cancel_cooldown() {
+        let mut contract = setup();
+        contract.set_intent_cancel_cooldown_ms(600_000);
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_buyer_policy(&mut contract, deposit_id, BuyerPolicy::ManualApproval);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+
+        set_context(&seller(), 0, NOW_MS);
+        contract.reject_intent(intent_hash);
+        assert_eq!(
+            contract.get_buyer_deposit_cooldown_until_ms(buyer(), deposit_id),
+            Some(NOW_MS + 600_000)
+        );
+
+        signal(&mut contract, &buyer(), deposit_id, 20, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Intent has expired")]
+    fn expired_pending_intent_cannot_be_approved() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_buyer_policy(&mut contract, deposit_id, BuyerPolicy::ManualApproval);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+
+        set_context(
+            &seller(),
+            0,
+            NOW_MS + contract.intent_expiration_period / 1_000_000,
+        );
+        contract.approve_intent(intent_hash);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn only_deposit_manager_approves() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_buyer_policy(&mut contract, deposit_id, BuyerPolicy::ManualApproval);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+
+        contract.approve_intent(intent_hash);
+    }
+
+    fn fulfill(contract: &mut Contract, intent_hash: &str, now_ms: u64) -> PromiseOrValue<()> {
+        set_context(&accounts(4), 0, now_ms);
+        contract.fulfill_intent(intent_hash.to_string())
+    }
+
+    fn release(contract: &mut Contract, intent_hash: &str, now_ms: u64) {
+        set_context(&seller(), 0, now_ms);
+        contract.release_intent(intent_hash.to_string());
+    }
+
+    #[test]
+    fn unattested_fulfillment_is_not_counted_in_stats() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+        fulfill(&mut contract, &intent_hash, NOW_MS + 1_000);
+
+        for stats in [
+            contract.get_account_stats(buyer()).buyer,
+            contract.get_account_stats(seller()).seller,
+        ] {
+            assert_eq!(stats.signaled, 1);
+            assert_eq!(stats.fulfilled, 0);
+            assert!(stats.volume.is_empty());
+        }
+    }
+
+    #[test]
+    fn held_unattested_payout_is_counted_when_dispute_resolved_as_fulfilled() {
+        let mut contract = setup();
+        contract.set_dispute_window_ms(3_600_000);
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+        fulfill(&mut contract, &intent_hash, NOW_MS + 1_000);
+
+        set_context(&seller(), 0, NOW_MS + 2_000);
+        contract.open_dispute(intent_hash.clone(), Vec::new());
+        set_context(&owner(), 0, NOW_MS + 3_000);
+        contract.resolve_dispute(intent_hash, DisputeOutcome::Fulfill, "paid".into());
+
+        let stats = contract.get_account_stats(buyer()).buyer;
+        assert_eq!(stats.fulfilled, 1);
+        assert_eq!(stats.disputed, 1);
+        assert_eq!(stats.total_time_to_fulfill_ms, 1_000);
+        assert_eq!(contract.get_account_stats(seller()).seller.fulfilled, 1);
+    }
+
+    #[test]
+    fn fulfillment_pays_out_immediately_by_default() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+
+        assert!(matches!(
+            fulfill(&mut contract, &intent_hash, NOW_MS),
+            PromiseOrValue::Promise(_)
+        ));
+        assert_eq!(contract.get_intent_payout_release_at_ms(intent_hash), None);
+    }
+
+    #[test]
+    fn held_payout_is_claimable_after_the_dispute_window() {
+        let mut contract = setup();
+        contract.set_dispute_window_ms(3_600_000);
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(
+            &mut contract,
+            &buyer(),
+            deposit_id,
+            50,
+            INTENT_STORAGE_DEPOSIT_YOCTO + ONE_NEAR,
+        );
+
+        assert!(matches!(
+            fulfill(&mut contract, &intent_hash, NOW_MS),
+            PromiseOrValue::Value(())
+        ));
+        assert!(transfers().is_empty());
+        assert_eq!(
+            contract.get_intent_collateral(intent_hash.clone()).0,
+            ONE_NEAR
+        );
+        assert_eq!(
+            contract.get_intent_payout_release_at_ms(intent_hash.clone()),
+            Some(NOW_MS + 3_600_000)
+        );
+
+        set_context(&accounts(4), 0, NOW_MS + 3_600_000);
+        contract.claim_intent_payout(intent_hash.clone());
+        assert_eq!(transfers(), vec![(buyer(), ONE_NEAR)]);
+        assert_eq!(contract.get_intent_collateral(intent_hash.clone()).0, 0);
+        assert_eq!(
+            contract.get_intent_payout_release_at_ms(intent_hash.clone()),
+            None
+        );
+        assert_eq!(
+            contract.get_intent(intent_hash).unwrap().status,
+            IntentStatus::Fulfilled
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Dispute window has not elapsed")]
+    fn held_payout_cannot_be_claimed_inside_the_window() {
+        let mut contract = setup();
+        contract.set_dispute_window_ms(3_600_000);
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+        fulfill(&mut contract, &intent_hash, NOW_MS);
+
+        set_context(&accounts(4), 0, NOW_MS + 3_599_999);
+        contract.claim_intent_payout(intent_hash);
+    }
+
+    #[test]
+    fn dispute_on_signaled_intent_resolved_as_fulfilled() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(
+            &mut contract,
+            &buyer(),
+            deposit_id,
+            50,
+            INTENT_STORAGE_DEPOSIT_YOCTO + ONE_NEAR,
+        );
+
+        set_context(&buyer(), 0, NOW_MS);
+        contract.open_dispute(intent_hash.clone(), vec!["ab".repeat(32)]);
+        assert_eq!(
+            contract.get_intent(intent_hash.clone()).unwrap().status,
+            IntentStatus::Disputed
+        );
+        assert_eq!(contract.get_deposit_active_intents(deposit_id).len(), 1);
+
+        set_context(&owner(), 0, NOW_MS + 1_000);
+        let result =
+            contract.resolve_dispute(intent_hash.clone(), DisputeOutcome::Fulfill, "paid".into());
+
+        assert!(matches!(result, PromiseOrValue::Promise(_)));
+        assert_eq!(transfers(), vec![(buyer(), ONE_NEAR)]);
+        assert_eq!(
+            contract.get_intent(intent_hash.clone()).unwrap().status,
+            IntentStatus::Fulfilled
+        );
+        let deposit = contract.get_deposit(deposit_id).unwrap();
+        assert_eq!(deposit.remaining_deposits, 950);
+        assert_eq!(deposit.outstanding_intents, 0);
+        assert!(contract.get_deposit_active_intents(deposit_id).is_empty());
+        let dispute = contract.get_intent_dispute(intent_hash).unwrap();
+        assert_eq!(dispute.outcome, Some(DisputeOutcome::Fulfill));
+        assert_eq!(dispute.resolved_by, Some(owner()));
+        assert_eq!(contract.get_account_stats(buyer()).buyer.fulfilled, 1);
+    }
+
+    #[test]
+    fn dispute_on_held_payout_resolved_as_cancelled() {
+        let mut contract = setup();
+        contract.set_dispute_window_ms(3_600_000);
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(
+            &mut contract,
+            &buyer(),
+            deposit_id,
+            50,
+            INTENT_STORAGE_DEPOSIT_YOCTO + ONE_NEAR,
+        );
+        fulfill(&mut contract, &intent_hash, NOW_MS + 1_000);
+        assert!(transfers().is_empty());
+        assert_eq!(contract.get_account_stats(buyer()).buyer.fulfilled, 0);
+
+        set_context(&seller(), 0, NOW_MS + 2_000);
+        contract.open_dispute(intent_hash.clone(), Vec::new());
+
+        set_context(&owner(), 0, NOW_MS + 3_000);
+        let result =
+            contract.resolve_dispute(intent_hash.clone(), DisputeOutcome::Cancel, "unpaid".into());
+
+        assert!(matches!(result, PromiseOrValue::Value(())));
+        assert_eq!(transfers(), vec![(seller(), ONE_NEAR)]);
+        assert_eq!(contract.get_intent_collateral(intent_hash.clone()).0, 0);
+        assert_eq!(
+            contract.get_intent(intent_hash.clone()).unwrap().status,
+            IntentStatus::Cancelled
+        );
+        assert_eq!(contract.get_intent_payout_release_at_ms(intent_hash), None);
+        let deposit = contract.get_deposit(deposit_id).unwrap();
+        assert_eq!(deposit.remaining_deposits, 1_000);
+        assert_eq!(deposit.outstanding_intents, 0);
+        let stats = contract.get_account_stats(buyer()).buyer;
+        assert_eq!(stats.fulfilled, 0);
+        assert_eq!(stats.cancelled, 1);
+        assert_eq!(stats.disputed, 1);
+        assert_eq!(stats.total_time_to_fulfill_ms, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dispute window has elapsed")]
+    fn held_payout_cannot_be_disputed_after_the_window() {
+        let mut contract = setup();
+        contract.set_dispute_window_ms(3_600_000);
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+        fulfill(&mut contract, &intent_hash, NOW_MS);
+
+        set_context(&seller(), 0, NOW_MS + 3_600_000);
+        contract.open_dispute(intent_hash, Vec::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only arbiter can resolve disputes")]
+    fn only_arbiter_resolves_disputes() {
+        let mut contract = setup();
+        contract.set_arbiter_account_id(Some(accounts(5)));
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+        contract.open_dispute(intent_hash.clone(), Vec::new());
+
+        set_context(&owner(), 0, NOW_MS);
+        contract.resolve_dispute(intent_hash, DisputeOutcome::Cancel, "unpaid".into());
+    }
+
+    #[test]
+    fn stats_track_fulfilled_cancelled_and_expired_intents() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+
+        let fulfilled = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+        release(&mut contract, &fulfilled, NOW_MS + 4_000);
+        let cancelled = signal(&mut contract, &buyer(), deposit_id, 20, 0);
+        contract.cancel_intent(cancelled);
+        let expired = signal(&mut contract, &buyer(), deposit_id, 30, 0);
+        set_context(
+            &accounts(4),
+            0,
+            NOW_MS + contract.intent_expiration_period / 1_000_000,
+        );
+        contract.expire_intent(expired);
+
+        let stats = contract.get_account_stats(buyer());
+        for side in [&stats.buyer, &contract.get_account_stats(seller()).seller] {
+            assert_eq!(side.signaled, 3);
+            assert_eq!(side.fulfilled, 1);
+            assert_eq!(side.cancelled, 1);
+            assert_eq!(side.expired, 1);
+            assert_eq!(side.disputed, 0);
+            assert_eq!(side.volume.len(), 1);
+            assert_eq!(side.volume[0].token, "usdc");
+            assert_eq!(side.volume[0].amount, 50);
+            assert_eq!(side.total_time_to_fulfill_ms, 4_000);
+            assert_eq!(side.average_time_to_fulfill_ms, 4_000);
+        }
+        assert_eq!(stats.seller.signaled, 0);
+        assert_eq!(contract.get_account_stats(seller()).buyer.signaled, 0);
+    }
+
+    #[test]
+    fn average_time_to_fulfill_spans_fulfillments() {
+        let mut stats = IntentStats::default();
+        stats.record_fulfillment("usdc", 10, 1_000);
+        stats.record_fulfillment("usdc", 5, 2_001);
+        stats.record_fulfillment("usdt", 7, 0);
+        assert_eq!(stats.fulfilled, 3);
+        assert_eq!(stats.average_time_to_fulfill_ms, 1_000);
+        assert_eq!(stats.volume[0].amount, 15);
+        assert_eq!(stats.volume[1].amount, 7);
+
+        stats.revert_fulfillment("usdc", 5, 2_001);
+        assert_eq!(stats.fulfilled, 2);
+        assert_eq!(stats.average_time_to_fulfill_ms, 500);
+        assert_eq!(stats.volume[0].amount, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Buyer does not meet deposit reputation requirement")]
+    fn buyer_below_reputation_threshold_is_rejected() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_context(&seller(), 0, NOW_MS);
+        contract.set_deposit_min_buyer_fulfilled_intents(deposit_id, 1);
+
+        signal(&mut contract, &buyer(), deposit_id, 50, 0);
+    }
+
+    #[test]
+    fn buyer_meeting_reputation_threshold_may_signal() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        let gated_deposit_id = create_deposit(&mut contract, 100);
+        contract.set_deposit_min_buyer_fulfilled_intents(gated_deposit_id, 1);
+
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+        release(&mut contract, &intent_hash, NOW_MS);
+
+        signal(&mut contract, &buyer(), gated_deposit_id, 50, 0);
+        assert_eq!(contract.get_account_stats(buyer()).buyer.signaled, 2);
+    }
+
+    /// Drops the deposit's active intent set and rewinds the backfill cursor,
+    /// as for deposits signaled before active intents were tracked.
+    fn forget_active_intents(contract: &mut Contract, deposit_id: u64) {
+        if let Some(mut set) = contract.deposit_active_intents.remove(&deposit_id) {
+            set.clear();
+        }
+        contract.active_intents_backfill_next = 1;
+        contract.active_intents_backfill_last = contract.deposit_counter;
+    }
+
+    #[test]
+    fn backfill_rebuilds_active_intents_of_older_deposits() {
+        let mut contract = setup();
+        let first_deposit_id = create_deposit(&mut contract, 100);
+        let second_deposit_id = create_deposit(&mut contract, 100);
+        let active = signal(&mut contract, &buyer(), first_deposit_id, 50, 0);
+        let cancelled = signal(&mut contract, &buyer(), first_deposit_id, 50, 0);
+        contract.cancel_intent(cancelled);
+        let other = signal(&mut contract, &buyer(), second_deposit_id, 50, 0);
+        forget_active_intents(&mut contract, first_deposit_id);
+        forget_active_intents(&mut contract, second_deposit_id);
+
+        set_context(&owner(), 0, NOW_MS);
+        assert_eq!(contract.backfill_deposit_active_intents(Some(1)), 2);
+        assert_eq!(
+            contract.get_deposit_active_intents(first_deposit_id),
+            vec![active]
+        );
+        assert!(contract
+            .get_deposit_active_intents(second_deposit_id)
+            .is_empty());
+        assert_eq!(contract.backfill_deposit_active_intents(Some(1)), 3);
+        assert_eq!(
+            contract.get_deposit_active_intents(second_deposit_id),
+            vec![other]
+        );
+        assert_eq!(contract.backfill_deposit_active_intents(None), 3);
+
+        contract.max_intents_per_deposit = 2;
+        signal(&mut contract, &buyer(), first_deposit_id, 50, 0);
+        assert_eq!(
+            contract.get_deposit_active_intents(first_deposit_id).len(),
+            2
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Deposit active intents are still being rebuilt")]
+    fn deposits_awaiting_backfill_cannot_take_intents() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        forget_active_intents(&mut contract, deposit_id);
+
+        signal(&mut contract, &buyer(), deposit_id, 50, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max intents reached")]
+    fn backfilled_deposits_enforce_max_intents() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        signal(&mut contract, &buyer(), deposit_id, 50, 0);
+        forget_active_intents(&mut contract, deposit_id);
+        set_context(&owner(), 0, NOW_MS);
+        contract.backfill_deposit_active_intents(None);
+        contract.max_intents_per_deposit = 1;
+
+        signal(&mut contract, &buyer(), deposit_id, 50, 0);
+    }
+
+    #[test]
+    fn gc_pages_through_history_while_removing() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        let mut kept = Vec::new();
+        for index in 0..7 {
+            let intent_hash = signal(&mut contract, &buyer(), deposit_id, 10, 0);
+            if index % 3 == 1 {
+                kept.push(intent_hash);
+            } else {
+                contract.cancel_intent(intent_hash);
+            }
+        }
+
+        set_context(&accounts(4), 0, NOW_MS + contract.get_intent_retention_ms());
+        let mut removed = 0;
+        let mut from_index = None;
+        let mut pages = 0;
+        loop {
+            let page = contract.gc_deposit_intents(deposit_id, from_index, Some(2));
+            removed += page.removed;
+            pages += 1;
+            match page.next_index {
+                Some(next_index) => from_index = Some(next_index),
+                None => break,
+            }
+        }
+
+        assert!(pages > 1);
+        assert_eq!(removed, 5);
+        let mut remaining = contract.get_deposit_intents(deposit_id, None, None);
+        remaining.sort();
+        kept.sort();
+        assert_eq!(remaining, kept);
+    }
+
+    #[test]
+    fn intent_retention_runs_from_when_the_intent_closed() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        let intent_hash = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+        let closed_at_ms = NOW_MS + 60_000;
+        set_context(&buyer(), 0, closed_at_ms);
+        contract.cancel_intent(intent_hash.clone());
+
+        let retention_ms = contract.get_intent_retention_ms();
+        set_context(&accounts(4), 0, closed_at_ms + retention_ms - 1);
+        assert_eq!(
+            contract.gc_deposit_intents(deposit_id, None, None).removed,
+            0
+        );
+        assert!(contract.get_intent(intent_hash.clone()).is_some());
+
+        set_context(&accounts(4), 0, closed_at_ms + retention_ms);
+        assert_eq!(
+            contract.gc_deposit_intents(deposit_id, None, None).removed,
+            1
+        );
+        assert!(contract.get_intent(intent_hash).is_none());
+        assert!(contract
+            .get_deposit_intents(deposit_id, None, None)
+            .is_empty());
+    }
+
+    #[test]
+    fn gc_keeps_active_intents_and_held_payouts() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        set_context(&owner(), 0, NOW_MS);
+        contract.set_dispute_window_ms(1_000);
+        let held = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+        fulfill(&mut contract, &held, NOW_MS);
+        let active = signal(&mut contract, &buyer(), deposit_id, 50, 0);
+
+        set_context(
+            &accounts(4),
+            0,
+            NOW_MS + contract.get_intent_retention_ms() + 1,
+        );
+        assert_eq!(
+            contract.gc_deposit_intents(deposit_id, None, None).removed,
+            0
+        );
+        assert!(contract.get_intent(held).is_some());
+        assert!(contract.get_intent(active).is_some());
+    }
+
+    #[test]
+    fn gc_removes_collected_intents_from_their_group() {
+        let mut contract = setup();
+        let first_deposit_id = create_deposit(&mut contract, 100);
+        let second_deposit_id = create_deposit(&mut contract, 100);
+        set_context(&buyer(), 0, NOW_MS);
+        let group_id = contract.signal_intent_batch(
+            [first_deposit_id, second_deposit_id]
+                .into_iter()
+                .map(|deposit_id| IntentLeg {
+                    deposit_id,
+                    amount: U128(50),
+                    payment_method: VENMO.to_string(),
+                })
+                .collect(),
+            "usd".to_string(),
+            "buyer.near".to_string(),
+            "near".to_string(),
+            None,
+        );
+        contract.cancel_intent_group(group_id.clone());
+        let second_hash = contract
+            .get_intent_group(group_id.clone())
+            .unwrap()
+            .intent_hashes[1]
+            .clone();
+
+        set_context(&accounts(4), 0, NOW_MS + contract.get_intent_retention_ms());
+        assert_eq!(
+            contract
+                .gc_deposit_intents(first_deposit_id, None, None)
+                .removed,
+            1
+        );
+        let group = contract.get_intent_group(group_id.clone()).unwrap();
+        assert_eq!(group.intent_hashes, vec![second_hash]);
+
+        assert_eq!(
+            contract
+                .gc_deposit_intents(second_deposit_id, None, None)
+                .removed,
+            1
+        );
+        assert!(contract.get_intent_group(group_id.clone()).is_none());
+        assert!(contract.get_intent_group_intents(group_id).is_empty());
+    }
+
+    #[test]
+    fn intent_lists_are_capped_without_a_limit() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        for _ in 0..MAX_VIEW_LIMIT + 5 {
+            let intent_hash = signal(&mut contract, &buyer(), deposit_id, 10, 0);
+            contract.cancel_intent(intent_hash);
+        }
+
+        assert_eq!(
+            contract.get_deposit_intents(deposit_id, None, None).len(),
+            MAX_VIEW_LIMIT
+        );
+        assert_eq!(
+            contract
+                .get_deposit_intents(deposit_id, Some(MAX_VIEW_LIMIT as u64), None)
+                .len(),
+            5
+        );
+        assert_eq!(
+            contract
+                .get_deposit_intents(deposit_id, None, Some(20))
+                .len(),
+            20
+        );
+        assert_eq!(
+            contract.get_account_intents(buyer(), None, None).len(),
+            MAX_VIEW_LIMIT
+        );
+        assert_eq!(
+            contract
+                .get_account_intents(buyer(), Some(10), Some(20))
+                .len(),
+            20
+        );
+    }
+
+    fn signal_at(
+        contract: &mut Contract,
+        deposit_id: u64,
+        payment_method: &str,
+        chain: &str,
+        recipient: &str,
+        now_ms: u64,
+    ) -> String {
+        set_context(&buyer(), 0, now_ms);
+        contract.signal_intent(
+            deposit_id,
+            U128(10),
+            payment_method.to_string(),
+            "usd".to_string(),
+            recipient.to_string(),
+            chain.to_string(),
+            None,
+        )
+    }
+
+    fn record_hashes(page: &IntentPage) -> Vec<String> {
+        page.intents
+            .iter()
+            .map(|intent| intent.intent_hash.clone())
+            .collect()
+    }
+
+    #[test]
+    fn intent_records_filter_by_status_chain_platform_and_time() {
+        let mut contract = setup();
+        add_payment_method(&mut contract, "wise", &["USD"]);
+        let venmo_deposit_id = create_deposit(&mut contract, 100);
+        set_context(&seller(), 0, NOW_MS);
+        let wise_deposit_id = contract.create_deposit(
+            "usdc".to_string(),
+            U128(1_000),
+            U128(10),
+            U128(500),
+            vec!["wise::seller".to_string()],
+            None,
+            Some(usd_rates(100)),
+        );
+
+        let signaled = signal_at(
+            &mut contract,
+            venmo_deposit_id,
+            VENMO,
+            "near",
+            "buyer.near",
+            NOW_MS,
+        );
+        let cancelled = signal_at(
+            &mut contract,
+            venmo_deposit_id,
+            VENMO,
+            "base",
+            &format!("0x{}", "11".repeat(20)),
+            NOW_MS + 1_000,
+        );
+        contract.cancel_intent(cancelled.clone());
+        let wise = signal_at(
+            &mut contract,
+            wise_deposit_id,
+            "wise::seller",
+            "near",
+            "buyer.near",
+            NOW_MS + 2_000,
+        );
+
+        let records = |filter: IntentFilter| {
+            record_hashes(&contract.get_account_intent_records(buyer(), Some(filter), None, None))
+        };
+        assert_eq!(
+            records(IntentFilter::default()),
+            vec![signaled.clone(), cancelled.clone(), wise.clone()]
+        );
+        assert_eq!(
+            records(IntentFilter {
+                statuses: Some(vec![IntentStatus::Cancelled]),
+                ..Default::default()
+            }),
+            vec![cancelled.clone()]
+        );
+        assert_eq!(
+            records(IntentFilter {
+                chain: Some(" BASE ".to_string()),
+                ..Default::default()
+            }),
+            vec![cancelled.clone()]
+        );
+        assert_eq!(
+            records(IntentFilter {
+                platform: Some("Wise".to_string()),
+                ..Default::default()
+            }),
+            vec![wise.clone()]
+        );
+        assert_eq!(
+            records(IntentFilter {
+                from_ms: Some(NOW_MS + 1_000),
+                to_ms: Some(NOW_MS + 2_000),
+                ..Default::default()
+            }),
+            vec![cancelled]
+        );
+        assert_eq!(
+            record_hashes(&contract.get_deposit_intent_records(
+                venmo_deposit_id,
+                Some(IntentFilter {
+                    statuses: Some(vec![IntentStatus::Signaled]),
+                    ..Default::default()
+                }),
+                None,
+                None,
+            )),
+            vec![signaled]
+        );
+        assert!(contract
+            .get_deposit_intent_records(wise_deposit_id + 1, None, None, None)
+            .intents
+            .is_empty());
+    }
+
+    #[test]
+    fn intent_records_page_with_next_index() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        let hashes: Vec<String> = (0..5)
+            .map(|_| signal(&mut contract, &buyer(), deposit_id, 10, 0))
+            .collect();
+        contract.cancel_intent(hashes[1].clone());
+        contract.cancel_intent(hashes[3].clone());
+
+        let page = contract.get_deposit_intent_records(deposit_id, None, None, Some(2));
+        assert_eq!(record_hashes(&page), hashes[..2].to_vec());
+        assert_eq!(page.next_index, Some(2));
+        let page = contract.get_deposit_intent_records(deposit_id, None, Some(4), Some(2));
+        assert_eq!(record_hashes(&page), hashes[4..].to_vec());
+        assert_eq!(page.next_index, None);
+
+        let cancelled = Some(IntentFilter {
+            statuses: Some(vec![IntentStatus::Cancelled]),
+            ..Default::default()
+        });
+        let page = contract.get_account_intent_records(buyer(), cancelled.clone(), None, Some(1));
+        assert_eq!(record_hashes(&page), vec![hashes[1].clone()]);
+        assert_eq!(page.next_index, Some(2));
+        let page =
+            contract.get_account_intent_records(buyer(), cancelled.clone(), Some(2), Some(1));
+        assert_eq!(record_hashes(&page), vec![hashes[3].clone()]);
+        assert_eq!(page.next_index, Some(4));
+        let page = contract.get_account_intent_records(buyer(), cancelled, Some(4), Some(1));
+        assert!(page.intents.is_empty());
+        assert_eq!(page.next_index, None);
+    }
+
+    fn funding(status: FundingStatus) -> DepositFundingMeta {
+        DepositFundingMeta {
+            asset_id: "usdc".to_string(),
+            refund_to: "seller.near".to_string(),
+            quote_id: None,
+            deposit_address: None,
+            deposit_memo: None,
+            quote_expires_at_ms: 0,
+            quote_generation: 0,
+            funding_started_at_ms: 0,
+            topup_deadline_at_ms: 0,
+            status,
+            funded_amount: 0,
+            origin_tx_hash: None,
+            last_intents_status: None,
+            failure_reason: None,
+            updated_at_ms: NOW_MS,
+        }
+    }
+
+    fn deposits_in(contract: &Contract, status: FundingStatus) -> Vec<u64> {
+        let mut deposit_ids = contract.get_deposits_by_funding_status_v2(status, None, None);
+        deposit_ids.sort_unstable();
+        deposit_ids
+    }
+
+    /// Funding records written before the status index existed, awaiting backfill.
+    fn unindexed_funding(contract: &mut Contract, statuses: &[FundingStatus]) {
+        for status in statuses {
+            contract.deposit_counter += 1;
+            contract
+                .deposit_funding
+                .insert(&contract.deposit_counter, &funding(status.clone()));
+        }
+        contract.funding_status_backfill_next = 1;
+        contract.funding_status_backfill_last = contract.deposit_counter;
+    }
+
+    #[test]
+    fn funding_status_index_follows_status_transitions() {
+        let mut contract = setup();
+        contract.store_deposit_funding(1, &funding(FundingStatus::AwaitingFunding));
+        contract.store_deposit_funding(2, &funding(FundingStatus::AwaitingFunding));
+        contract.store_deposit_funding(1, &funding(FundingStatus::Funded));
+        assert_eq!(
+            deposits_in(&contract, FundingStatus::AwaitingFunding),
+            vec![2]
+        );
+        assert_eq!(deposits_in(&contract, FundingStatus::Funded), vec![1]);
+
+        contract.store_deposit_funding(1, &funding(FundingStatus::Funded));
+        contract.store_deposit_funding(2, &funding(FundingStatus::Cancelled));
+        assert!(deposits_in(&contract, FundingStatus::AwaitingFunding).is_empty());
+        assert!(contract
+            .deposits_by_funding_status
+            .get(&FundingStatus::AwaitingFunding)
+            .is_none());
+        assert_eq!(deposits_in(&contract, FundingStatus::Funded), vec![1]);
+        assert_eq!(deposits_in(&contract, FundingStatus::Cancelled), vec![2]);
+    }
+
+    #[test]
+    fn funding_status_backfill_pages_through_older_deposits() {
+        let mut contract = setup();
+        unindexed_funding(
+            &mut contract,
+            &[
+                FundingStatus::Funded,
+                FundingStatus::AwaitingFunding,
+                FundingStatus::Funded,
+                FundingStatus::Failed,
+                FundingStatus::Funded,
+            ],
+        );
+        contract.deposit_counter += 1;
+        contract.store_deposit_funding(6, &funding(FundingStatus::Funded));
+        contract.store_deposit_funding(2, &funding(FundingStatus::Funded));
+
+        assert_eq!(contract.backfill_funding_status_index(Some(2)), 3);
+        assert_eq!(contract.backfill_funding_status_index(Some(2)), 5);
+        assert_eq!(contract.backfill_funding_status_index(Some(2)), 6);
+        assert_eq!(contract.backfill_funding_status_index(Some(2)), 6);
+
+        assert_eq!(
+            deposits_in(&contract, FundingStatus::Funded),
+            vec![1, 2, 3, 5, 6]
+        );
+        assert_eq!(deposits_in(&contract, FundingStatus::Failed), vec![4]);
+        assert!(deposits_in(&contract, FundingStatus::AwaitingFunding).is_empty());
+        assert_eq!(
+            contract.get_deposits_by_funding_status_v2(FundingStatus::Funded, Some(3), Some(1)),
+            contract
+                .deposits_by_funding_status
+                .get(&FundingStatus::Funded)
+                .unwrap()
+                .as_vector()
+                .get(3)
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Funding status index is still being backfilled")]
+    fn funding_status_query_waits_for_backfill() {
+        let mut contract = setup();
+        unindexed_funding(
+            &mut contract,
+            &[FundingStatus::Funded, FundingStatus::Funded],
+        );
+        contract.backfill_funding_status_index(Some(1));
+
+        contract.get_deposits_by_funding_status_v2(FundingStatus::Funded, None, None);
+    }
+
+    fn list_deposit(contract: &mut Contract, rate: u128) -> u64 {
+        let deposit_id = create_deposit(contract, rate);
+        contract.add_open_listing("usdc", deposit_id);
+        deposit_id
+    }
+
+    fn order_book(
+        contract: &Contract,
+        buyer: Option<AccountId>,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> OrderBookPage {
+        contract.get_order_book(
+            "usdc".to_string(),
+            "usd".to_string(),
+            None,
+            None,
+            buyer,
+            from_index,
+            limit,
+        )
+    }
+
+    fn quoted_deposit_ids(page: &OrderBookPage) -> Vec<u64> {
+        page.entries.iter().map(|entry| entry.deposit_id).collect()
+    }
+
+    #[test]
+    fn order_book_pages_through_open_deposits_cheapest_first() {
+        let mut contract = setup();
+        let expensive = list_deposit(&mut contract, 300);
+        let cheap = list_deposit(&mut contract, 100);
+        let mid = list_deposit(&mut contract, 200);
+
+        let page = order_book(&contract, None, None, None);
+        assert_eq!(quoted_deposit_ids(&page), vec![cheap, mid, expensive]);
+        assert_eq!(page.next_index, None);
+
+        let first = order_book(&contract, None, None, Some(2));
+        assert_eq!(quoted_deposit_ids(&first), vec![cheap, expensive]);
+        assert_eq!(first.next_index, Some(2));
+        let second = order_book(&contract, None, first.next_index, Some(2));
+        assert_eq!(quoted_deposit_ids(&second), vec![mid]);
+        assert_eq!(second.next_index, None);
+
+        let best = contract
+            .get_best_quote("usdc".to_string(), "USD".to_string(), U128(50), None, None)
+            .unwrap();
+        assert_eq!(best.deposit_id, cheap);
+        assert_eq!(best.conversion_rate, 100);
+    }
+
+    #[test]
+    fn order_book_skips_deposits_the_buyer_may_not_signal_on() {
+        let mut contract = setup();
+        let open = list_deposit(&mut contract, 300);
+        let allowlisted = list_deposit(&mut contract, 100);
+        set_buyer_policy(&mut contract, allowlisted, BuyerPolicy::Allowlist);
+        let gated = list_deposit(&mut contract, 200);
+        contract.set_deposit_min_buyer_fulfilled_intents(gated, 1);
+
+        let anonymous = order_book(&contract, None, None, None);
+        assert_eq!(quoted_deposit_ids(&anonymous), vec![open]);
+        let listed = order_book(&contract, Some(accounts(4)), None, None);
+        assert_eq!(quoted_deposit_ids(&listed), vec![allowlisted, open]);
+
+        let intent_hash = signal(&mut contract, &buyer(), open, 50, 0);
+        release(&mut contract, &intent_hash, NOW_MS);
+        let reputable = order_book(&contract, Some(buyer()), None, None);
+        assert_eq!(quoted_deposit_ids(&reputable), vec![gated, open]);
+
+        let best = contract
+            .get_best_quote(
+                "usdc".to_string(),
+                "usd".to_string(),
+                U128(50),
+                None,
+                Some(buyer()),
+            )
+            .unwrap();
+        assert_eq!(best.deposit_id, gated);
+    }
+
+    fn legs(deposit_ids: &[u64], amount: u128) -> Vec<IntentLeg> {
+        deposit_ids
+            .iter()
+            .map(|deposit_id| IntentLeg {
+                deposit_id: *deposit_id,
+                amount: U128(amount),
+                payment_method: VENMO.to_string(),
+            })
+            .collect()
+    }
+
+    fn signal_batch(
+        contract: &mut Contract,
+        deposit_ids: &[u64],
+        amount: u128,
+        attached_yocto: u128,
+    ) -> String {
+        set_context(&buyer(), attached_yocto, NOW_MS);
+        contract.signal_intent_batch(
+            legs(deposit_ids, amount),
+            "usd".to_string(),
+            "buyer.near".to_string(),
+            "near".to_string(),
+            None,
+        )
+    }
+
+    #[test]
+    fn intent_batch_signals_every_leg_and_splits_collateral() {
+        let mut contract = setup();
+        let deposit_ids: Vec<u64> = (0..3).map(|_| create_deposit(&mut contract, 100)).collect();
+        let attached = 3 * INTENT_STORAGE_DEPOSIT_YOCTO + 100;
+
+        let group_id = signal_batch(&mut contract, &deposit_ids, 500, attached);
+        let intents = contract.get_intent_group_intents(group_id.clone());
+        assert_eq!(intents.len(), 3);
+        for (intent, deposit_id) in intents.iter().zip(&deposit_ids) {
+            assert_eq!(intent.deposit_id, *deposit_id);
+            assert_eq!(intent.amount, 500);
+            assert_eq!(intent.status, IntentStatus::Signaled);
+        }
+        let collateral: Vec<u128> = intents
+            .iter()
+            .map(|intent| contract.intent_collateral.get(&intent.intent_hash).unwrap())
+            .collect();
+        assert_eq!(collateral, vec![34, 33, 33]);
+        assert_eq!(contract.get_intent_group(group_id).unwrap().buyer, buyer());
+    }
+
+    #[test]
+    #[should_panic(expected = "Intent batch exceeds max intents per buyer")]
+    fn intent_batch_must_fit_under_the_buyer_cap() {
+        let mut contract = setup();
+        let deposit_ids: Vec<u64> = (0..3).map(|_| create_deposit(&mut contract, 100)).collect();
+        set_context(&owner(), 0, NOW_MS);
+        contract.set_max_intents_per_buyer(3);
+        signal(&mut contract, &buyer(), deposit_ids[0], 50, 0);
+
+        signal_batch(&mut contract, &deposit_ids, 50, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate deposit in intent batch")]
+    fn intent_batch_rejects_duplicate_deposits() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+
+        signal_batch(&mut contract, &[deposit_id, deposit_id], 50, 0);
+    }
+
+    #[test]
+    fn cancel_intent_group_cancels_open_legs_only() {
+        let mut contract = setup();
+        let deposit_ids: Vec<u64> = (0..2).map(|_| create_deposit(&mut contract, 100)).collect();
+        let group_id = signal_batch(&mut contract, &deposit_ids, 50, 0);
+        let hashes = contract
+            .get_intent_group(group_id.clone())
+            .unwrap()
+            .intent_hashes;
+        fulfill(&mut contract, &hashes[0], NOW_MS);
+
+        set_context(&buyer(), 0, NOW_MS);
+        contract.cancel_intent_group(group_id.clone());
+        let statuses: Vec<IntentStatus> = contract
+            .get_intent_group_intents(group_id)
+            .into_iter()
+            .map(|intent| intent.status)
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![IntentStatus::Fulfilled, IntentStatus::Cancelled]
+        );
+        assert_eq!(
+            contract
+                .get_deposit(deposit_ids[1])
+                .unwrap()
+                .remaining_deposits,
+            1_000
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only buyer can cancel")]
+    fn cancel_intent_group_is_buyer_only() {
+        let mut contract = setup();
+        let deposit_ids: Vec<u64> = (0..2).map(|_| create_deposit(&mut contract, 100)).collect();
+        let group_id = signal_batch(&mut contract, &deposit_ids, 50, 0);
+
+        set_context(&seller(), 0, NOW_MS);
+        contract.cancel_intent_group(group_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "No cancellable intents in group")]
+    fn cancel_intent_group_needs_an_open_leg() {
+        let mut contract = setup();
+        let deposit_ids: Vec<u64> = (0..2).map(|_| create_deposit(&mut contract, 100)).collect();
+        let group_id = signal_batch(&mut contract, &deposit_ids, 50, 0);
+
+        set_context(&buyer(), 0, NOW_MS);
+        contract.cancel_intent_group(group_id.clone());
+        contract.cancel_intent_group(group_id);
+    }
+
+    const EVM_RECIPIENT: &str = "0x3535353535353535353535353535353535353535";
+
+    fn signal_to(contract: &mut Contract, deposit_id: u64, recipient: &str, chain: &str) -> String {
+        set_context(&buyer(), 0, NOW_MS);
+        contract.signal_intent(
+            deposit_id,
+            U128(50),
+            VENMO.to_string(),
+            "usd".to_string(),
+            recipient.to_string(),
+            chain.to_string(),
+            None,
+        )
+    }
+
+    fn register_usdc(contract: &mut Contract, chain_id: u64, decimals: u8, deposit_decimals: u8) {
+        set_context(&owner(), 0, NOW_MS);
+        contract.register_evm_token(
+            "usdc".to_string(),
+            chain_id,
+            Some("0xA0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".to_string()),
+            decimals,
+            deposit_decimals,
+            "USDC".to_string(),
+        );
+    }
+
+    fn evm_payout(contract: &Contract, intent_hash: String) -> EvmPayoutView {
+        contract.get_intent_evm_payout(
+            intent_hash,
+            0,
+            U128(1_000_000_000),
+            U128(30_000_000_000),
+            65_000,
+        )
+    }
+
+    #[test]
+    fn evm_payout_pays_the_signaled_recipient() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        register_usdc(&mut contract, 1, 6, 6);
+        let intent_hash = signal_to(&mut contract, deposit_id, EVM_RECIPIENT, "eth");
+        fulfill(&mut contract, &intent_hash, NOW_MS);
+
+        let payout = evm_payout(&contract, intent_hash);
+        assert_eq!(payout.chain_id, 1);
+        assert_eq!(
+            payout.token_contract.as_deref(),
+            Some("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48")
+        );
+        assert_eq!(payout.recipient, EVM_RECIPIENT);
+        assert_eq!(payout.amount, U128(50));
+        assert_eq!(payout.token_amount, U128(50));
+        assert_eq!(
+            contract.get_evm_token("usdc".to_string()).unwrap().decimals,
+            6
+        );
+    }
+
+    #[test]
+    fn evm_payout_rescales_to_the_token_decimals() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        // BSC USDC has 18 decimals against 6 on NEAR.
+        register_usdc(&mut contract, 56, 18, 6);
+        let intent_hash = signal_to(&mut contract, deposit_id, EVM_RECIPIENT, "bsc");
+        fulfill(&mut contract, &intent_hash, NOW_MS);
+
+        let payout = evm_payout(&contract, intent_hash);
+        assert_eq!(payout.amount, U128(50));
+        assert_eq!(payout.token_amount, U128(50_000_000_000_000));
+        let mut fewer = contract.get_evm_token("usdc".to_string()).unwrap();
+        fewer.decimals = 4;
+        fewer.deposit_decimals = 5;
+        assert_eq!(Contract::evm_token_amount(50, &fewer), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Intent amount is not representable in the EVM token's decimals")]
+    fn evm_payout_refuses_lossy_rescaling() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        register_usdc(&mut contract, 1, 6, 8);
+        let intent_hash = signal_to(&mut contract, deposit_id, EVM_RECIPIENT, "eth");
+        fulfill(&mut contract, &intent_hash, NOW_MS);
+
+        evm_payout(&contract, intent_hash);
+    }
+
+    #[test]
+    #[should_panic(expected = "Intent chain does not match the EVM token's chain")]
+    fn evm_token_deposits_require_the_token_chain() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        register_usdc(&mut contract, 8453, 6, 6);
+
+        signal_to(&mut contract, deposit_id, EVM_RECIPIENT, "arbitrum");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid EVM recipient")]
+    fn l2_chains_validate_evm_recipients() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+
+        signal_to(&mut contract, deposit_id, "buyer.near", "base");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid EVM recipient")]
+    fn evm_token_deposits_reject_non_evm_recipients() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+        register_usdc(&mut contract, 1, 6, 6);
+
+        signal_to(&mut contract, deposit_id, "buyer.near", "near");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid EVM recipient")]
+    fn evm_chain_rejects_malformed_recipients() {
+        let mut contract = setup();
+        let deposit_id = create_deposit(&mut contract, 100);
+
+        signal_to(&mut contract, deposit_id, "0xtest123", "ETH");
+    }
 }
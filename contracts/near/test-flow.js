@@ -127,7 +127,7 @@ async function testFlow() {
         amount: "20000000000000000000000",
         payment_method: "wise",
         currency_code: "USD",
-        recipient: "tb1qbuyeraddressxxxxxxxxxxxxxxxxxxxxxxx",
+        recipient: "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
         chain: "BTC",
       },
       { attachedDeposit: "10000000000000000000000" },
@@ -186,7 +186,7 @@ async function testFlow() {
         amount: "50000000",
         payment_method: "venmo",
         currency_code: "USD",
-        recipient: "0xtest123",
+        recipient: "0x3535353535353535353535353535353535353535",
         chain: "ETH",
       },
       { attachedDeposit: "10000000000000000000000" },
@@ -313,7 +313,7 @@ async function main() {
             amount: minAmount,
             payment_method: paymentMethod,
             currency_code: "USD",
-            recipient: "tb1qautomationtestrecipient",
+            recipient: "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
             chain: "BTC",
         },
         gas: GAS,
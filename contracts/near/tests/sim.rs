@@ -1,4 +1,4 @@
-use near_workspaces::{types::NearToken, Account, Contract};
+use near_workspaces::types::NearToken;
 use serde_json::json;
 
 #[tokio::test]
@@ -0,0 +1,153 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use borsh::BorshSerialize;
+
+use crate::types::{AttestationChecks, AttestationRecord};
+
+pub const ATTESTATION_VERSION_V1: &str = "anypay/tlsn-attestation/v1";
+pub const ATTESTATION_VERSION_V2: &str = "anypay/tlsn-attestation/v2";
+
+#[derive(BorshSerialize)]
+struct CanonicalMessageV2<'a> {
+    version: &'a str,
+    session_id: &'a str,
+    intent_id: Option<&'a str>,
+    server_name: &'a str,
+    expected_memo: Option<&'a str>,
+    expected_amount: Option<&'a str>,
+    expected_currency: Option<&'a str>,
+    expected_platform: Option<&'a str>,
+    expected_tagname: Option<&'a str>,
+    transcript_digest_sha256: &'a str,
+    issued_at_ms: u64,
+    expires_at_ms: u64,
+    payment_at_ms: Option<u64>,
+    checks: AttestationChecks,
+}
+
+/// Returns the bytes to sign for `record.version`, or `None` if the version is unknown.
+pub fn canonical_message(record: &AttestationRecord) -> Option<Vec<u8>> {
+    match record.version.trim() {
+        ATTESTATION_VERSION_V1 => Some(canonical_message_v1(record).into_bytes()),
+        ATTESTATION_VERSION_V2 => Some(canonical_message_v2(record)),
+        _ => None,
+    }
+}
+
+/// Legacy `key=value\n` message. Newlines inside values are replaced with
+/// spaces, so distinct values can sign identically; kept only for transition.
+pub fn canonical_message_v1(record: &AttestationRecord) -> String {
+    format!(
+        "version={}\nsession_id={}\nintent_id={}\nserver_name={}\nexpected_memo={}\nexpected_amount={}\nexpected_currency={}\nexpected_platform={}\nexpected_tagname={}\ntranscript_digest_sha256={}\nissued_at_ms={}\nexpires_at_ms={}\npolicy_passed={}\n",
+        ATTESTATION_VERSION_V1,
+        sanitize(&record.session_id),
+        sanitize(record.intent_id.as_deref().unwrap_or("")),
+        sanitize(&record.server_name),
+        sanitize(record.expected_memo.as_deref().unwrap_or("")),
+        sanitize(record.expected_amount.as_deref().unwrap_or("")),
+        sanitize(record.expected_currency.as_deref().unwrap_or("")),
+        sanitize(record.expected_platform.as_deref().unwrap_or("")),
+        sanitize(record.expected_tagname.as_deref().unwrap_or("")),
+        sanitize(&record.transcript_digest_sha256),
+        record.issued_at_ms,
+        record.expires_at_ms,
+        record.checks.policy_passed,
+    )
+}
+
+/// Borsh-encoded message: every string is length-prefixed and every optional
+/// field is tagged, so each distinct set of field values signs distinct bytes.
+pub fn canonical_message_v2(record: &AttestationRecord) -> Vec<u8> {
+    let message = CanonicalMessageV2 {
+        version: ATTESTATION_VERSION_V2,
+        session_id: &record.session_id,
+        intent_id: record.intent_id.as_deref(),
+        server_name: &record.server_name,
+        expected_memo: record.expected_memo.as_deref(),
+        expected_amount: record.expected_amount.as_deref(),
+        expected_currency: record.expected_currency.as_deref(),
+        expected_platform: record.expected_platform.as_deref(),
+        expected_tagname: record.expected_tagname.as_deref(),
+        transcript_digest_sha256: &record.transcript_digest_sha256,
+        issued_at_ms: record.issued_at_ms,
+        expires_at_ms: record.expires_at_ms,
+        payment_at_ms: record.payment_at_ms,
+        checks: record.checks,
+    };
+    borsh::to_vec(&message).expect("borsh serialization into a Vec cannot fail")
+}
+
+fn sanitize(value: &str) -> String {
+    value.replace('\n', " ").trim().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AttestationSignature;
+
+    fn record(memo: &str, tagname: &str) -> AttestationRecord {
+        AttestationRecord {
+            attestation_id: "attestation:session-1".into(),
+            version: ATTESTATION_VERSION_V2.into(),
+            session_id: "session-1".into(),
+            intent_id: Some("intent:7".into()),
+            server_name: "wise.com".into(),
+            expected_memo: Some(memo.into()),
+            expected_amount: Some("10.00".into()),
+            expected_currency: Some("USD".into()),
+            expected_platform: Some("wise".into()),
+            expected_tagname: Some(tagname.into()),
+            transcript_digest_sha256: "00".into(),
+            issued_at_ms: 1,
+            expires_at_ms: 2,
+            payment_at_ms: None,
+            checks: AttestationChecks {
+                recv_body_revealed: true,
+                memo_match: Some(true),
+                amount_match: Some(true),
+                currency_match: Some(true),
+                platform_match: Some(true),
+                tagname_match: Some(true),
+                policy_passed: true,
+            },
+            signature: AttestationSignature {
+                algorithm: "ed25519".into(),
+                public_key_hex: String::new(),
+                signature_hex: String::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn v1_collides_on_newlines_but_v2_does_not() {
+        let plain = record("anypay:1:7", "alice");
+        let newline = record("anypay:1:7\n", "alice");
+
+        assert_eq!(canonical_message_v1(&plain), canonical_message_v1(&newline));
+        assert_ne!(canonical_message_v2(&plain), canonical_message_v2(&newline));
+    }
+
+    #[test]
+    fn v2_distinguishes_shifted_field_boundaries() {
+        let left = record("anypay:1:7", "alice");
+        let right = record("anypay:1:7a", "lice");
+
+        assert_ne!(canonical_message_v2(&left), canonical_message_v2(&right));
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let mut unknown = record("m", "t");
+        unknown.version = "anypay/tlsn-attestation/v0".into();
+        assert!(canonical_message(&unknown).is_none());
+
+        let mut legacy = record("m", "t");
+        legacy.version = ATTESTATION_VERSION_V1.into();
+        assert_eq!(
+            canonical_message(&legacy),
+            Some(canonical_message_v1(&legacy).into_bytes())
+        );
+    }
+}
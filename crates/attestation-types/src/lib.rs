@@ -0,0 +1,19 @@
+//! Attestation types shared by the NEAR contract and the attestation backend:
+//! the signed record, its canonical message encodings, signature checks and
+//! the payee details hash preimage.
+#![no_std]
+
+extern crate alloc;
+
+mod canonical;
+mod payee;
+mod types;
+mod verify;
+
+pub use canonical::{
+    canonical_message, canonical_message_v1, canonical_message_v2, ATTESTATION_VERSION_V1,
+    ATTESTATION_VERSION_V2,
+};
+pub use payee::payee_details_preimage;
+pub use types::{AttestationChecks, AttestationRecord, AttestationSignature};
+pub use verify::{decode_hex_fixed, verify_signature, VerifyError, SIGNATURE_ALGORITHM_ED25519};
@@ -0,0 +1,42 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use borsh::BorshSerialize;
+
+#[derive(BorshSerialize)]
+struct PayeeDetailsPreimage<'a> {
+    platform: &'a str,
+    tagname: &'a str,
+    salt: &'a str,
+}
+
+/// Bytes whose sha256 is a deposit's `payee_details_hash`.
+///
+/// The platform is lowercased and every field trimmed, matching how the
+/// contract normalizes `platform::tagname`. The salt keeps short handles from
+/// being brute-forced out of the published hash.
+pub fn payee_details_preimage(platform: &str, tagname: &str, salt: &str) -> Vec<u8> {
+    let platform: String = platform.trim().to_lowercase();
+    let preimage = PayeeDetailsPreimage {
+        platform: &platform,
+        tagname: tagname.trim(),
+        salt: salt.trim(),
+    };
+    borsh::to_vec(&preimage).expect("borsh serialization into a Vec cannot fail")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preimage_normalizes_and_separates_fields() {
+        assert_eq!(
+            payee_details_preimage(" Wise ", "@alice ", " salt"),
+            payee_details_preimage("wise", "@alice", "salt")
+        );
+        assert_ne!(
+            payee_details_preimage("wise", "@alice", "salt"),
+            payee_details_preimage("wise", "@alices", "alt")
+        );
+    }
+}
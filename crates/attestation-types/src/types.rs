@@ -0,0 +1,44 @@
+use alloc::string::String;
+use borsh::BorshSerialize;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationSignature {
+    pub algorithm: String,
+    pub public_key_hex: String,
+    pub signature_hex: String,
+}
+
+/// Policy check results. v1 only signs `policy_passed`; v2 signs all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, Serialize, Deserialize)]
+pub struct AttestationChecks {
+    pub recv_body_revealed: bool,
+    pub memo_match: Option<bool>,
+    pub amount_match: Option<bool>,
+    pub currency_match: Option<bool>,
+    pub platform_match: Option<bool>,
+    pub tagname_match: Option<bool>,
+    pub policy_passed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationRecord {
+    pub attestation_id: String,
+    pub version: String,
+    pub session_id: String,
+    pub intent_id: Option<String>,
+    pub server_name: String,
+    pub expected_memo: Option<String>,
+    pub expected_amount: Option<String>,
+    pub expected_currency: Option<String>,
+    pub expected_platform: Option<String>,
+    pub expected_tagname: Option<String>,
+    pub transcript_digest_sha256: String,
+    pub issued_at_ms: u64,
+    pub expires_at_ms: u64,
+    /// Payment time extracted from the transcript. Only signed by v2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_at_ms: Option<u64>,
+    pub checks: AttestationChecks,
+    pub signature: AttestationSignature,
+}
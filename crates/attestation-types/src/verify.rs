@@ -0,0 +1,102 @@
+use core::fmt;
+
+use crate::canonical::canonical_message;
+use crate::types::AttestationRecord;
+
+pub const SIGNATURE_ALGORITHM_ED25519: &str = "ed25519";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    UnsupportedVersion,
+    UnsupportedAlgorithm,
+    UntrustedSigner,
+    InvalidSignatureHex,
+    InvalidPublicKeyHex,
+    BadSignature,
+}
+
+impl VerifyError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UnsupportedVersion => "unsupported attestation version",
+            Self::UnsupportedAlgorithm => "unsupported attestation signature algorithm",
+            Self::UntrustedSigner => "attestation signer is not trusted",
+            Self::InvalidSignatureHex => "invalid attestation signature hex",
+            Self::InvalidPublicKeyHex => "invalid attestation public key hex",
+            Self::BadSignature => "attestation signature verification failed",
+        }
+    }
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Checks that `record` is signed by `trusted_public_key_hex`.
+///
+/// The ed25519 primitive is injected so the contract can use the host
+/// function (`env::ed25519_verify`) while off-chain callers use a library.
+pub fn verify_signature<F>(
+    record: &AttestationRecord,
+    trusted_public_key_hex: &str,
+    ed25519_verify: F,
+) -> Result<(), VerifyError>
+where
+    F: FnOnce(&[u8; 64], &[u8], &[u8; 32]) -> bool,
+{
+    if !record
+        .signature
+        .algorithm
+        .trim()
+        .eq_ignore_ascii_case(SIGNATURE_ALGORITHM_ED25519)
+    {
+        return Err(VerifyError::UnsupportedAlgorithm);
+    }
+    if !record
+        .signature
+        .public_key_hex
+        .trim()
+        .eq_ignore_ascii_case(trusted_public_key_hex.trim())
+    {
+        return Err(VerifyError::UntrustedSigner);
+    }
+
+    let signature = decode_hex_fixed::<64>(&record.signature.signature_hex)
+        .ok_or(VerifyError::InvalidSignatureHex)?;
+    let public_key = decode_hex_fixed::<32>(&record.signature.public_key_hex)
+        .ok_or(VerifyError::InvalidPublicKeyHex)?;
+    let message = canonical_message(record).ok_or(VerifyError::UnsupportedVersion)?;
+
+    if ed25519_verify(&signature, &message, &public_key) {
+        Ok(())
+    } else {
+        Err(VerifyError::BadSignature)
+    }
+}
+
+pub fn decode_hex_fixed<const N: usize>(value: &str) -> Option<[u8; N]> {
+    let trimmed = value.trim();
+    if trimmed.len() != N * 2 {
+        return None;
+    }
+
+    let bytes = trimmed.as_bytes();
+    let mut out = [0u8; N];
+    for (index, byte) in out.iter_mut().enumerate() {
+        let high = decode_hex_nibble(bytes[index * 2])?;
+        let low = decode_hex_nibble(bytes[index * 2 + 1])?;
+        *byte = (high << 4) | low;
+    }
+    Some(out)
+}
+
+fn decode_hex_nibble(ch: u8) -> Option<u8> {
+    match ch {
+        b'0'..=b'9' => Some(ch - b'0'),
+        b'a'..=b'f' => Some(ch - b'a' + 10),
+        b'A'..=b'F' => Some(ch - b'A' + 10),
+        _ => None,
+    }
+}
@@ -0,0 +1,145 @@
+//! A record signed the way the backend signs must verify the way the
+//! contract verifies, after a round trip through the JSON it is relayed as.
+//! The contract's own tests also run backend-signed records through
+//! `fulfill_intent_with_attestation` and its `env::ed25519_verify` adapter.
+
+use anypay_attestation_types::{
+    canonical_message, verify_signature, AttestationChecks, AttestationRecord,
+    AttestationSignature, VerifyError, ATTESTATION_VERSION_V1, ATTESTATION_VERSION_V2,
+};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+fn signing_key() -> SigningKey {
+    SigningKey::from_bytes(&[7u8; 32])
+}
+
+fn public_key_hex(key: &SigningKey) -> String {
+    hex(key.verifying_key().as_bytes())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn dalek_verify(signature: &[u8; 64], message: &[u8], public_key: &[u8; 32]) -> bool {
+    VerifyingKey::from_bytes(public_key)
+        .map(|key| {
+            key.verify(message, &Signature::from_bytes(signature))
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+fn signed_record(version: &str, key: &SigningKey) -> AttestationRecord {
+    let mut record = AttestationRecord {
+        attestation_id: "attestation:session-42".to_string(),
+        version: version.to_string(),
+        session_id: "session-42".to_string(),
+        intent_id: Some("intent:3".to_string()),
+        server_name: "wise.com".to_string(),
+        expected_memo: Some("anypay:1:3".to_string()),
+        expected_amount: Some("125.50".to_string()),
+        expected_currency: Some("EUR".to_string()),
+        expected_platform: Some("wise".to_string()),
+        expected_tagname: Some("@alice".to_string()),
+        transcript_digest_sha256: "ab".repeat(32),
+        issued_at_ms: 1_700_000_000_000,
+        expires_at_ms: 1_700_000_600_000,
+        payment_at_ms: None,
+        checks: AttestationChecks {
+            recv_body_revealed: true,
+            memo_match: Some(true),
+            amount_match: Some(true),
+            currency_match: Some(true),
+            platform_match: Some(true),
+            tagname_match: Some(true),
+            policy_passed: true,
+        },
+        signature: AttestationSignature {
+            algorithm: "ed25519".to_string(),
+            public_key_hex: public_key_hex(key),
+            signature_hex: String::new(),
+        },
+    };
+
+    let message = canonical_message(&record).expect("known version");
+    record.signature.signature_hex = hex(&key.sign(&message).to_bytes());
+    record
+}
+
+fn relay(record: &AttestationRecord) -> AttestationRecord {
+    let json = serde_json::to_string(record).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn backend_signed_v2_record_verifies_in_contract() {
+    let key = signing_key();
+    let record = relay(&signed_record(ATTESTATION_VERSION_V2, &key));
+
+    assert_eq!(
+        verify_signature(&record, &public_key_hex(&key), dalek_verify),
+        Ok(())
+    );
+}
+
+#[test]
+fn legacy_v1_record_still_verifies() {
+    let key = signing_key();
+    let record = relay(&signed_record(ATTESTATION_VERSION_V1, &key));
+
+    assert_eq!(
+        verify_signature(&record, &public_key_hex(&key), dalek_verify),
+        Ok(())
+    );
+}
+
+#[test]
+fn v2_signs_payment_timestamp() {
+    let key = signing_key();
+    let mut record = signed_record(ATTESTATION_VERSION_V2, &key);
+    record.payment_at_ms = Some(1_700_000_100_000);
+
+    assert_eq!(
+        verify_signature(&relay(&record), &public_key_hex(&key), dalek_verify),
+        Err(VerifyError::BadSignature)
+    );
+}
+
+#[test]
+fn tampered_v2_check_is_rejected() {
+    let key = signing_key();
+    let mut record = signed_record(ATTESTATION_VERSION_V2, &key);
+    record.checks.memo_match = Some(false);
+
+    assert_eq!(
+        verify_signature(&relay(&record), &public_key_hex(&key), dalek_verify),
+        Err(VerifyError::BadSignature)
+    );
+}
+
+#[test]
+fn untrusted_signer_is_rejected() {
+    let key = signing_key();
+    let other = SigningKey::from_bytes(&[9u8; 32]);
+    let record = relay(&signed_record(ATTESTATION_VERSION_V2, &key));
+
+    assert_eq!(
+        verify_signature(&record, &public_key_hex(&other), dalek_verify),
+        Err(VerifyError::UntrustedSigner)
+    );
+}
+
+#[test]
+fn record_without_payment_timestamp_parses() {
+    let key = signing_key();
+    let mut json = serde_json::to_value(signed_record(ATTESTATION_VERSION_V1, &key)).unwrap();
+    json.as_object_mut().unwrap().remove("payment_at_ms");
+
+    let record: AttestationRecord = serde_json::from_value(json).unwrap();
+    assert_eq!(record.payment_at_ms, None);
+    assert_eq!(
+        verify_signature(&record, &public_key_hex(&key), dalek_verify),
+        Ok(())
+    );
+}
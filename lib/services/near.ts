@@ -20,6 +20,8 @@ const V2_STORAGE_FEE_YOCTO =
     process.env.NEXT_PUBLIC_NEAR_V2_STORAGE_FEE_YOCTO || "50000000000000000000000"; // 0.05 NEAR
 const DEPOSIT_FLOW_MODE = process.env.NEXT_PUBLIC_DEPOSIT_FLOW === "legacy" ? "legacy" : "v2";
 const STORAGE_COST_PER_BYTE_YOCTO = BigInt("10000000000000000000");
+// Matches the contract's MAX_VIEW_LIMIT, the most intent hashes one view call returns.
+const INTENT_VIEW_PAGE_SIZE = 200;
 
 function normalizeAmountInput(value: string): string {
     return String(value ?? "").trim().replace(",", ".");
@@ -909,8 +911,8 @@ export class NearService {
         const account = accountId || this.accountId;
         if (!account) throw new Error("No account specified");
 
-        // Contract returns Vec<String> (Intent Hashes)
-        const intentHashes: string[] = await this.view<string[]>('get_account_intents', { account_id: account });
+        // Contract returns Vec<String> (Intent Hashes), capped per call
+        const intentHashes = await this.viewIntentHashes('get_account_intents', { account_id: account });
 
         // Fetch full intent details for each hash
         const intents = await Promise.all(
@@ -921,8 +923,8 @@ export class NearService {
     }
 
     async getDepositIntents(depositId: number): Promise<IntentRecord[]> {
-        // Contract returns Vec<String> (Intent Hashes)
-        const intentHashes: string[] = await this.view<string[]>('get_deposit_intents', { deposit_id: depositId });
+        // Contract returns Vec<String> (Intent Hashes), capped per call
+        const intentHashes = await this.viewIntentHashes('get_deposit_intents', { deposit_id: depositId });
 
         const intents = await Promise.all(
             intentHashes.map(hash => this.getIntent(hash))
@@ -931,6 +933,22 @@ export class NearService {
         return intents.filter((i): i is IntentRecord => i !== null);
     }
 
+    private async viewIntentHashes(
+        methodName: 'get_account_intents' | 'get_deposit_intents',
+        args: Record<string, unknown>,
+    ): Promise<string[]> {
+        const hashes: string[] = [];
+        for (;;) {
+            const page = await this.view<string[]>(methodName, {
+                ...args,
+                from_index: hashes.length,
+                limit: INTENT_VIEW_PAGE_SIZE,
+            });
+            hashes.push(...page);
+            if (page.length < INTENT_VIEW_PAGE_SIZE) return hashes;
+        }
+    }
+
     async getPaymentMethod(name: string) {
         return await this.view('get_payment_method', { name });
     }
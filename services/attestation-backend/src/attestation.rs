@@ -2,6 +2,10 @@ use std::collections::HashMap;
 use std::env;
 use std::sync::Arc;
 
+use anypay_attestation_types::{
+    canonical_message, AttestationChecks, AttestationRecord, AttestationSignature,
+    ATTESTATION_VERSION_V2, SIGNATURE_ALGORITHM_ED25519,
+};
 use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
 use ed25519_dalek::{Signer, SigningKey};
@@ -10,47 +14,9 @@ use sha2::{Digest, Sha256};
 use tokio::sync::RwLock;
 use tracing::warn;
 
-const ATTESTATION_VERSION: &str = "anypay/tlsn-attestation/v1";
 const DEFAULT_TTL_MS: u64 = 10 * 60 * 1000;
 const DEV_SEED_FALLBACK: &str = "anypay-attestation-backend-dev-key";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AttestationSignature {
-    pub algorithm: String,
-    pub public_key_hex: String,
-    pub signature_hex: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AttestationChecks {
-    pub recv_body_revealed: bool,
-    pub memo_match: Option<bool>,
-    pub amount_match: Option<bool>,
-    pub currency_match: Option<bool>,
-    pub platform_match: Option<bool>,
-    pub tagname_match: Option<bool>,
-    pub policy_passed: bool,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AttestationRecord {
-    pub attestation_id: String,
-    pub version: String,
-    pub session_id: String,
-    pub intent_id: Option<String>,
-    pub server_name: String,
-    pub expected_memo: Option<String>,
-    pub expected_amount: Option<String>,
-    pub expected_currency: Option<String>,
-    pub expected_platform: Option<String>,
-    pub expected_tagname: Option<String>,
-    pub transcript_digest_sha256: String,
-    pub issued_at_ms: u64,
-    pub expires_at_ms: u64,
-    pub checks: AttestationChecks,
-    pub signature: AttestationSignature,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublicKeyView {
     pub algorithm: String,
@@ -128,7 +94,7 @@ impl AttestationService {
 
     pub fn public_key_view(&self) -> PublicKeyView {
         PublicKeyView {
-            algorithm: "ed25519".to_string(),
+            algorithm: SIGNATURE_ALGORITHM_ED25519.to_string(),
             public_key_hex: self.public_key_hex.clone(),
         }
     }
@@ -222,25 +188,12 @@ impl AttestationService {
         let expires_at_ms = issued_at_ms.saturating_add(self.ttl_ms);
         let attestation_id = format!("attestation:{}", session_id);
 
-        let canonical_message = canonical_message(&CanonicalMessage {
-            session_id: &session_id,
-            intent_id: intent_id.as_deref(),
-            server_name: &payload.server_name,
-            expected_memo: expected_memo.as_deref(),
-            expected_amount: expected_amount.as_deref(),
-            expected_currency: expected_currency.as_deref(),
-            expected_platform: expected_platform.as_deref(),
-            expected_tagname: expected_tagname.as_deref(),
-            transcript_digest_sha256: &transcript_digest_sha256,
-            issued_at_ms,
-            expires_at_ms,
-            policy_passed,
-        });
+        // The verifier does not extract a payment timestamp from the transcript yet.
+        let payment_at_ms = None;
 
-        let signature = self.signing_key.sign(canonical_message.as_bytes());
-        let record = AttestationRecord {
+        let mut record = AttestationRecord {
             attestation_id,
-            version: ATTESTATION_VERSION.to_string(),
+            version: ATTESTATION_VERSION_V2.to_string(),
             session_id: session_id.clone(),
             intent_id: intent_id.clone(),
             server_name: payload.server_name,
@@ -252,13 +205,18 @@ impl AttestationService {
             transcript_digest_sha256,
             issued_at_ms,
             expires_at_ms,
+            payment_at_ms,
             checks,
             signature: AttestationSignature {
-                algorithm: "ed25519".to_string(),
+                algorithm: SIGNATURE_ALGORITHM_ED25519.to_string(),
                 public_key_hex: self.public_key_hex.clone(),
-                signature_hex: hex::encode(signature.to_bytes()),
+                signature_hex: String::new(),
             },
         };
+        let canonical_message = canonical_message(&record)
+            .ok_or_else(|| anyhow!("unsupported attestation version"))?;
+        let signature = self.signing_key.sign(&canonical_message);
+        record.signature.signature_hex = hex::encode(signature.to_bytes());
 
         let mut store = self.store.write().await;
         store
@@ -343,41 +301,3 @@ fn build_search_text(payload: &VerifierWebhookPayload) -> String {
     }
     combined
 }
-
-struct CanonicalMessage<'a> {
-    session_id: &'a str,
-    intent_id: Option<&'a str>,
-    server_name: &'a str,
-    expected_memo: Option<&'a str>,
-    expected_amount: Option<&'a str>,
-    expected_currency: Option<&'a str>,
-    expected_platform: Option<&'a str>,
-    expected_tagname: Option<&'a str>,
-    transcript_digest_sha256: &'a str,
-    issued_at_ms: u64,
-    expires_at_ms: u64,
-    policy_passed: bool,
-}
-
-fn canonical_message(message: &CanonicalMessage<'_>) -> String {
-    format!(
-        "version={}\nsession_id={}\nintent_id={}\nserver_name={}\nexpected_memo={}\nexpected_amount={}\nexpected_currency={}\nexpected_platform={}\nexpected_tagname={}\ntranscript_digest_sha256={}\nissued_at_ms={}\nexpires_at_ms={}\npolicy_passed={}\n",
-        ATTESTATION_VERSION,
-        sanitize(message.session_id),
-        sanitize(message.intent_id.unwrap_or("")),
-        sanitize(message.server_name),
-        sanitize(message.expected_memo.unwrap_or("")),
-        sanitize(message.expected_amount.unwrap_or("")),
-        sanitize(message.expected_currency.unwrap_or("")),
-        sanitize(message.expected_platform.unwrap_or("")),
-        sanitize(message.expected_tagname.unwrap_or("")),
-        sanitize(message.transcript_digest_sha256),
-        message.issued_at_ms,
-        message.expires_at_ms,
-        message.policy_passed,
-    )
-}
-
-fn sanitize(value: &str) -> String {
-    value.replace('\n', " ").trim().to_string()
-}